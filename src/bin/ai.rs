@@ -0,0 +1,193 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Computer player reflexes for Egyptian RatScrew
+
+//! How quickly and how well the computer player slaps.
+//!
+//! Reaction times follow an ex-Gaussian distribution, a normal curve with a
+//! long tail of slow reactions, which is how human reaction times are
+//! usually modelled. On top of that a player can miss a combination
+//! outright, slap a pile that has none, and takes longer to spot some
+//! combinations than others.
+
+use std::time::Duration;
+use rand::Rng;
+use rand::distributions::{Exp, IndependentSample, Normal};
+use Card;
+use rules::{RuleSet, SlapPattern};
+
+/// Fastest a slap can come after a card lands, in milliseconds
+const FASTEST: f64 = 150.0;
+
+/// How good the computer player is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Expert,
+}
+
+impl Difficulty {
+    /// Every difficulty, easiest first
+    pub fn all() -> [Difficulty; 4] {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard, Difficulty::Expert]
+    }
+
+    /// Lower case name of the difficulty
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+            Difficulty::Expert => "expert",
+        }
+    }
+
+    /// Finds a difficulty by name
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::all().iter().find(|d| d.name() == name).cloned()
+    }
+
+    /// Reflexes of a player at this difficulty
+    pub fn reflexes(self) -> Reflexes {
+        match self {
+            Difficulty::Easy => Reflexes { mean: 1600.0, spread: 400.0, tail: 600.0,
+                                           miss: 0.35, false_slap: 0.04 },
+            Difficulty::Normal => Reflexes { mean: 1100.0, spread: 300.0, tail: 400.0,
+                                             miss: 0.15, false_slap: 0.02 },
+            Difficulty::Hard => Reflexes { mean: 700.0, spread: 150.0, tail: 200.0,
+                                           miss: 0.05, false_slap: 0.01 },
+            Difficulty::Expert => Reflexes { mean: 450.0, spread: 80.0, tail: 100.0,
+                                             miss: 0.01, false_slap: 0.0 },
+        }
+    }
+}
+
+/// How quickly and how well a player spots combinations. Times are in
+/// milliseconds for spotting a pair, other patterns take longer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reflexes {
+    /// Middle of the normal part of the reaction time
+    pub mean: f64,
+    /// Standard deviation of the normal part
+    pub spread: f64,
+    /// Average length of the tail of slow reactions
+    pub tail: f64,
+    /// Chance of not noticing a combination at all
+    pub miss: f64,
+    /// Chance of slapping a pile without a combination
+    pub false_slap: f64,
+}
+
+impl Reflexes {
+    /// Decides whether to slap the pile and how long after the card
+    /// landed. None leaves the pile alone.
+    pub fn slap<R: Rng>(&self, rules: &RuleSet, pile: &[Card], rng: &mut R) -> Option<Duration> {
+        if pile.is_empty() {
+            return None;
+        }
+        let scale = match rules.find(pile) {
+            Some(found) => {
+                if rng.gen::<f64>() < self.miss {
+                    return None;
+                }
+                recognition(found.pattern)
+            }
+            // Slapping a pile by mistake comes from jumping the gun,
+            // so it is as quick as spotting a pair
+            None => {
+                if rng.gen::<f64>() >= self.false_slap {
+                    return None;
+                }
+                1.0
+            }
+        };
+        Some(Duration::from_millis(self.reaction(scale, rng) as u64))
+    }
+
+    /// Draws a reaction time in milliseconds, `scale` times as slow as
+    /// spotting a pair
+    fn reaction<R: Rng>(&self, scale: f64, rng: &mut R) -> f64 {
+        let normal = Normal::new(self.mean * scale, self.spread * scale).ind_sample(rng);
+        let tail = Exp::new(1.0 / (self.tail * scale)).ind_sample(rng);
+        (normal + tail).max(FASTEST)
+    }
+}
+
+impl Default for Reflexes {
+    fn default() -> Self {
+        Difficulty::default().reflexes()
+    }
+}
+
+/// How long a pattern takes to spot compared to a pair. Patterns made of
+/// cards further apart, or that need some arithmetic, take longer.
+fn recognition(pattern: SlapPattern) -> f64 {
+    match pattern {
+        SlapPattern::Ten | SlapPattern::Joker => 0.9,
+        SlapPattern::Pair => 1.0,
+        SlapPattern::SixtyNine | SlapPattern::Marriage => 1.2,
+        SlapPattern::Sandwich => 1.4,
+        SlapPattern::Divorce => 1.5,
+        SlapPattern::SixtyNineSandwich | SlapPattern::AddToTen => 1.6,
+        SlapPattern::Run => 1.8,
+        SlapPattern::TopBottom => 2.0,
+        SlapPattern::FourInARow => 2.2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, XorShiftRng};
+    use {Rank, Rank::*, Suit::*};
+
+    fn pile(ranks: &[Rank]) -> Vec<Card> {
+        ranks.iter().map(|r| Card::new(*r, Hearts)).collect()
+    }
+
+    // Average reaction in milliseconds over many tries, and how many
+    // of the tries slapped at all
+    fn average(reflexes: Reflexes, cards: &[Card]) -> (u64, usize) {
+        let mut rng = XorShiftRng::from_seed([1, 2, 3, 4]);
+        let slaps: Vec<Duration> = (0..2000)
+            .filter_map(|_| reflexes.slap(&RuleSet::classic(), cards, &mut rng))
+            .collect();
+        let total: u64 = slaps.iter().map(|d| d.as_secs() * 1000 + d.subsec_nanos() as u64 / 1_000_000).sum();
+        (total / slaps.len().max(1) as u64, slaps.len())
+    }
+
+    #[test]
+    fn test_harder_is_faster() {
+        let pair = pile(&[Num(3), Num(8), Num(8)]);
+        let (easy, easy_slaps) = average(Difficulty::Easy.reflexes(), &pair);
+        let (expert, expert_slaps) = average(Difficulty::Expert.reflexes(), &pair);
+
+        assert!(expert < easy);
+        assert!(expert >= FASTEST as u64);
+        assert!(expert_slaps > easy_slaps);
+    }
+
+    #[test]
+    fn test_runs_are_slower_than_pairs() {
+        let reflexes = Difficulty::Hard.reflexes();
+        let (pair, _) = average(reflexes, &pile(&[Num(3), Num(8), Num(8)]));
+        let (run, _) = average(reflexes, &pile(&[Num(3), Num(4), Num(5)]));
+
+        assert!(run > pair);
+    }
+
+    #[test]
+    fn test_false_slaps() {
+        let nothing = pile(&[Num(2), Num(9), Jack]);
+        let (_, expert) = average(Difficulty::Expert.reflexes(), &nothing);
+        let (_, easy) = average(Difficulty::Easy.reflexes(), &nothing);
+
+        assert_eq!(expert, 0);
+        assert!(easy > 0 && easy < 200);
+        assert_eq!(average(Difficulty::Easy.reflexes(), &[]).1, 0);
+    }
+}
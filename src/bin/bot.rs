@@ -0,0 +1,243 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Computer players for Egyptian RatScrew
+
+//! Computer players, and a registry to find them by name.
+//!
+//! A `Bot` is told about every event in the game and answers with a
+//! `Decision`. It only sees what a player at its seat could see through a
+//! `View`: the pile, how many cards everyone holds and whose turn it is,
+//! never the cards in anyone's hand.
+
+use std::time::Duration;
+use {GameRng, Pile};
+use ai::{Difficulty, Reflexes};
+use engine::{Challenge, Event, GameState};
+use rules::RuleSet;
+
+/// What a bot wants to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// Nothing
+    Pass,
+    /// Slap the pile straight away
+    Slap,
+    /// Slap the pile this long after the card landed, unless someone
+    /// beats the bot to it
+    SlapAfter(Duration),
+}
+
+/// The game as seen from one seat
+#[derive(Debug, Clone, Copy)]
+pub struct View<'a> {
+    game: &'a GameState,
+    seat: usize,
+}
+
+impl<'a> View<'a> {
+    pub fn new(game: &'a GameState, seat: usize) -> Self {
+        View {
+            game,
+            seat,
+        }
+    }
+
+    /// Seat of the bot looking at the game
+    pub fn seat(&self) -> usize {
+        self.seat
+    }
+
+    /// Number of seats at the table
+    pub fn num_players(&self) -> usize {
+        self.game.num_players()
+    }
+
+    /// Player whose turn it is
+    pub fn turn(&self) -> usize {
+        self.game.turn()
+    }
+
+    /// The pile in the middle
+    pub fn pile(&self) -> &'a Pile {
+        self.game.pile()
+    }
+
+    /// Face-card challenge in progress
+    pub fn challenge(&self) -> Option<Challenge> {
+        self.game.challenge()
+    }
+
+    /// Cards a player holds, face down
+    pub fn card_count(&self, player: usize) -> usize {
+        self.game.player(player).card_count()
+    }
+
+    /// Whether a player has left the game
+    pub fn has_left(&self, player: usize) -> bool {
+        self.game.has_left(player)
+    }
+
+    /// Combinations that can be slapped
+    pub fn rules(&self) -> &'a RuleSet {
+        self.game.rules()
+    }
+
+    /// Whether the bot may slap the pile right now
+    pub fn can_slap(&self) -> bool {
+        self.game.can_slap(self.seat)
+    }
+}
+
+/// A computer player
+pub trait Bot: Send {
+    // Tells the bot the cards are dealt
+    fn seated(&mut self, _view: &View) {}
+
+    // Tells the bot about something that happened in the game. Slaps
+    // are heeded after a card is played, while the pile is open. Bots
+    // that play by chance draw from `rng`, so a seed replays the game.
+    fn observe(&mut self, view: &View, event: &Event, rng: &mut GameRng) -> Decision;
+
+    // Tells the bot it's its turn. Bots can't hold on to their cards, so
+    // the card is played for it once it has looked.
+    fn turn(&mut self, _view: &View) {}
+}
+
+/// Plays every card straight away and slaps with human-like reflexes
+pub struct ReflexBot {
+    reflexes: Reflexes,
+}
+
+impl ReflexBot {
+    pub fn new(reflexes: Reflexes) -> Self {
+        ReflexBot { reflexes }
+    }
+}
+
+impl Bot for ReflexBot {
+    // Looks at the pile every time a card lands on it
+    fn observe(&mut self, view: &View, event: &Event, rng: &mut GameRng) -> Decision {
+        match *event {
+            Event::CardPlayed { .. } if view.can_slap() => {
+                match self.reflexes.slap(view.rules(), view.pile().cards(), rng) {
+                    Some(delay) => Decision::SlapAfter(delay),
+                    None => Decision::Pass,
+                }
+            }
+            _ => Decision::Pass,
+        }
+    }
+}
+
+/// Never slaps, for tests that need bots to keep their hands off the pile
+#[cfg(test)]
+pub struct Stubborn;
+
+#[cfg(test)]
+impl Bot for Stubborn {
+    fn observe(&mut self, _view: &View, _event: &Event, _rng: &mut GameRng) -> Decision {
+        Decision::Pass
+    }
+}
+
+/// Makes a fresh bot for every game
+pub type Maker = Box<dyn Fn() -> Box<dyn Bot> + Send + Sync>;
+
+/// Bots that can be seated by name
+pub struct Registry {
+    bots: Vec<(String, Maker)>,
+}
+
+impl Registry {
+    /// A registry without any bots
+    pub fn new() -> Self {
+        Registry { bots: Vec::new() }
+    }
+
+    /// The bots that come with the game, a `ReflexBot` for every
+    /// difficulty named after it
+    pub fn builtin() -> Self {
+        let mut registry = Registry::new();
+        for &difficulty in Difficulty::all().iter() {
+            registry.register(difficulty.name(), move || {
+                Box::new(ReflexBot::new(difficulty.reflexes()))
+            });
+        }
+        registry
+    }
+
+    /// Adds a bot, replacing any bot already registered under the name
+    pub fn register<F>(&mut self, name: &str, maker: F)
+        where F: Fn() -> Box<dyn Bot> + Send + Sync + 'static
+    {
+        self.bots.retain(|(known, _)| known != name);
+        self.bots.push((name.to_string(), Box::new(maker)));
+    }
+
+    /// Makes a new bot of the kind registered under the name
+    pub fn make(&self, name: &str) -> Option<Box<dyn Bot>> {
+        self.bots.iter()
+            .find(|&(known, _)| known == name)
+            .map(|(_, maker)| maker())
+    }
+
+    /// Names of every registered bot, in the order they were added
+    pub fn names(&self) -> Vec<&str> {
+        self.bots.iter().map(|(name, _)| name.as_ref()).collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::builtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Card, Deck};
+    use engine::Action;
+    use {Rank::*, Suit::*};
+
+    // Slaps whatever lands on the pile
+    struct Eager;
+
+    impl Bot for Eager {
+        fn observe(&mut self, _view: &View, event: &Event, _rng: &mut GameRng) -> Decision {
+            match *event {
+                Event::CardPlayed { .. } => Decision::Slap,
+                _ => Decision::Pass,
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::builtin();
+        assert_eq!(registry.names(), vec!["easy", "normal", "hard", "expert"]);
+        assert!(registry.make("normal").is_some());
+        assert!(registry.make("eager").is_none());
+
+        registry.register("eager", || Box::new(Eager));
+        registry.register("eager", || Box::new(Eager));
+        assert_eq!(registry.names().len(), 5);
+        assert!(registry.make("eager").is_some());
+    }
+
+    #[test]
+    fn test_bot_sees_the_game() {
+        let deck = vec![Card::new(Num(4), Hearts), Card::new(Num(4), Clubs),
+                        Card::new(Num(9), Hearts), Card::new(Num(2), Spades)];
+        let mut game = GameState::new(Deck::from(deck), 2, 0);
+        let events = game.apply(Action::PlayCard(0)).unwrap();
+        let view = View::new(&game, 1);
+
+        assert_eq!(view.pile().len(), 1);
+        assert_eq!(view.card_count(0), 1);
+        assert_eq!(view.turn(), 1);
+        assert_eq!(Eager.observe(&view, &events[0], &mut ::seeded(1)), Decision::Slap);
+        assert_eq!(Stubborn.observe(&view, &events[0], &mut ::seeded(1)), Decision::Pass);
+    }
+}
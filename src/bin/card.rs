@@ -1,11 +1,36 @@
 // MIT License
 // Copyright (c) 2018 Cole Phares
-// Last Modified: 5/22/2018
+// Last Modified: 10/18/2026
 // Server side for online card game Egyptian RatScrew
 
 // Code to create a shuffled deck of cards borrowed and modified from
 // http://cultofmetatron.io/2017/03/21/learning-rust-with-blackjack-part-1/
 
+extern crate rand;
+extern crate toml;
+#[cfg(feature = "serde")]
+extern crate serde;
+use rand::{Rng, SeedableRng, XorShiftRng};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+pub mod ai;
+pub mod bot;
+pub mod config;
+pub mod deck;
+pub mod engine;
+pub mod protocol;
+pub mod render;
+pub mod replay;
+pub mod rules;
+#[cfg(feature = "serde")]
+mod serialize;
+pub mod sim;
+
+pub use deck::{Deck, DeckSpec, Hand, Pile};
+pub use rules::{RuleSet, SlapMatch, SlapPattern};
+
 /// Suit of the card
 #[derive(Debug, Clone, Copy, Eq)]
 pub enum Suit {
@@ -18,6 +43,14 @@ pub enum Suit {
 use::Suit::*;
 
 impl Suit {
+    /// Hearts and Diamonds are red, Spades and Clubs are black
+    pub fn is_red(self) -> bool {
+        match self {
+            Hearts | Diamonds => true,
+            Spades | Clubs => false,
+        }
+    }
+
     // returns a string of the suit
     pub fn value(self) -> String {
         match self {
@@ -43,6 +76,7 @@ pub enum Rank {
     Queen,
     King,
     Ace,
+    Joker,
 }
 
 use Rank::*;
@@ -56,6 +90,7 @@ impl Rank {
             Queen => 12,
             King => 13,
             Ace => 1,
+            Joker => 0,
         }
     }
 }
@@ -85,8 +120,8 @@ impl PartialEq<u32> for Card {
 impl Card {
     pub fn new(rank: Rank, suit: Suit) -> Card {
         Card {
-            rank: rank,
-            suit: suit,
+            rank,
+            suit,
         }
     }
     
@@ -103,43 +138,15 @@ impl Card {
     }
 
     /// Determines the suit of the card
+    #[deprecated(note = "use `str::parse`, which returns an error instead of panicking")]
     pub fn get_suit(suit: String) -> Suit {
-        match suit.trim().to_string().as_ref() {
-            "Hearts" => Hearts,
-            "Diamonds" => Diamonds,
-            "Spades" => Spades,
-            "Clubs" => Clubs,
-            _ => panic!("Unexpected Suit {}", suit)
-        }
+        suit.trim().parse().unwrap_or_else(|_| panic!("Unexpected Suit {}", suit))
     }
 
     /// Returns a card from a String
+    #[deprecated(note = "use `str::parse`, which returns an error instead of panicking")]
     pub fn parse_card(card_string: String) -> Card {
-        // Gets the suit and rank in that order
-        let mut v: Vec<&str> = card_string.split(", ").collect();
-        let suit: String = v.pop()
-                            .unwrap()
-                            .to_string();
-        let rank: u32 = v.pop()
-                         .unwrap()
-                         .parse::<u32>()
-                         .unwrap();
-
-        let card: Card;
-        
-        // Adds the card to players hand
-        if rank > 1 && rank <= 10 {
-            card = Card::new(Num(rank), Card::get_suit(suit));
-        }else {
-            card = match rank {
-                11 => Card::new(Jack, Card::get_suit(suit)),
-                12 => Card::new(Queen, Card::get_suit(suit)),
-                13 => Card::new(King, Card::get_suit(suit)),
-                1 => Card::new(Ace, Card::get_suit(suit)),
-                _ => panic!("Unexpected Rank {}", rank)
-            };
-        }
-        card
+        card_string.parse().unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
@@ -148,8 +155,208 @@ impl std::fmt::Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self.rank {
             Num(n) => write!(f, "{} of {:?}", n, self.suit),
+            Joker if self.suit.is_red() => write!(f, "Red Joker"),
+            Joker => write!(f, "Black Joker"),
             _ => write!(f, "{:?} of {:?}", self.rank, self.suit),
         }
     }
 }
 
+/// What was wrong with text that should have been a card
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CardParseError {
+    /// There was nothing to read
+    Empty,
+    /// The rank isn't one we know
+    BadRank(String),
+    /// The suit isn't one we know
+    BadSuit(String),
+    /// The text isn't in any of the forms a card can be written in
+    BadFormat(String),
+}
+
+impl fmt::Display for CardParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CardParseError::Empty => write!(f, "No card given"),
+            CardParseError::BadRank(ref rank) => write!(f, "{} isn't a rank", rank),
+            CardParseError::BadSuit(ref suit) => write!(f, "{} isn't a suit", suit),
+            CardParseError::BadFormat(ref text) => write!(f, "Can't read a card from {}", text),
+        }
+    }
+}
+
+impl error::Error for CardParseError {
+    fn description(&self) -> &str {
+        "malformed card"
+    }
+}
+
+/// Names of the number cards from two up
+const NUMBER_NAMES: [&str; 9] = ["two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"];
+
+/// Reads a suit by name ("Hearts" or "heart"), by letter ("H") or by
+/// symbol ("♥"), ignoring case
+impl FromStr for Suit {
+    type Err = CardParseError;
+
+    fn from_str(text: &str) -> Result<Suit, CardParseError> {
+        let name = text.trim().to_lowercase();
+        match name.as_ref() {
+            "hearts" | "heart" | "h" | "♥" | "♡" => Ok(Hearts),
+            "diamonds" | "diamond" | "d" | "♦" | "♢" => Ok(Diamonds),
+            "spades" | "spade" | "s" | "♠" | "♤" => Ok(Spades),
+            "clubs" | "club" | "c" | "♣" | "♧" => Ok(Clubs),
+            _ => Err(CardParseError::BadSuit(text.trim().to_string())),
+        }
+    }
+}
+
+/// Reads a rank by name ("Queen", "ten"), by letter ("Q", "T" for ten, "X"
+/// for a joker) or by number, either the number on the card or the one
+/// `Rank::value` gives, ignoring case
+impl FromStr for Rank {
+    type Err = CardParseError;
+
+    fn from_str(text: &str) -> Result<Rank, CardParseError> {
+        let name = text.trim().to_lowercase();
+        let rank = match name.as_ref() {
+            "jack" | "j" | "11" => Jack,
+            "queen" | "q" | "12" => Queen,
+            "king" | "k" | "13" => King,
+            "ace" | "a" | "1" => Ace,
+            "joker" | "x" | "0" => Joker,
+            "t" => Num(10),
+            name => match NUMBER_NAMES.iter().position(|&number| number == name) {
+                Some(i) => Num(i as u32 + 2),
+                None => match name.parse() {
+                    Ok(n) if (2..=10).contains(&n) => Num(n),
+                    _ => return Err(CardParseError::BadRank(text.trim().to_string())),
+                },
+            },
+        };
+        Ok(rank)
+    }
+}
+
+/// Reads a card written any of these ways:
+///
+/// * "Ten of Hearts" or "10 of Hearts", as Display writes it, and "Red
+///   Joker" or "Black Joker"
+/// * "10, Hearts", as `card_to_string` writes it
+/// * "10H" or "TH", as the protocol and `render::code` write it
+/// * "10♥", as `render::Style::Symbols` writes it
+///
+/// Every card reads back from all of these the same as it was written,
+/// jokers included: a red joker comes back a joker of Hearts and a black
+/// joker a joker of Spades, the suits decks give them.
+impl FromStr for Card {
+    type Err = CardParseError;
+
+    fn from_str(text: &str) -> Result<Card, CardParseError> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(CardParseError::Empty);
+        }
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.len() == 2 && words[1].eq_ignore_ascii_case("joker") {
+            let suit = match words[0].to_lowercase().as_ref() {
+                "red" => Hearts,
+                "black" => Spades,
+                _ => return Err(CardParseError::BadFormat(text.to_string())),
+            };
+            return Ok(Card::new(Joker, suit));
+        }
+        if words.len() == 3 && words[1].eq_ignore_ascii_case("of") {
+            return Ok(Card::new(words[0].parse()?, words[2].parse()?));
+        }
+        if let Some(comma) = text.find(',') {
+            return Ok(Card::new(text[..comma].parse()?, text[comma + 1..].parse()?));
+        }
+
+        // Short codes end in the suit
+        match text.char_indices().last() {
+            Some((suit, _)) if suit > 0 && words.len() == 1 => {
+                Ok(Card::new(text[..suit].parse()?, text[suit..].parse()?))
+            }
+            _ => Err(CardParseError::BadFormat(text.to_string())),
+        }
+    }
+}
+
+/// Creates a standard 52 card deck
+pub fn make_deck() -> Deck {
+    DeckSpec::standard().build()
+}
+
+/// Random numbers for a game. Everything random in a game draws from
+/// numbers started from one seed, so the seed plays the same game again.
+pub type GameRng = XorShiftRng;
+
+/// Random numbers that start from the seed. XorShift can't start from all
+/// zeroes, so the seed is mixed with constants first.
+pub fn seeded(seed: u64) -> GameRng {
+    XorShiftRng::from_seed([seed as u32 ^ 0x193a_6754, (seed >> 32) as u32 ^ 0xa8a7_d469,
+                            0x9783_0e05, 0x113b_a7bb])
+}
+
+/// Picks a seed for a game nobody asked for by seed
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use render::{self, Style};
+
+    fn read(text: &str) -> (Rank, Suit) {
+        let card: Card = text.parse().unwrap();
+        (card.rank, card.suit)
+    }
+
+    #[test]
+    fn test_formats() {
+        for text in ["10, Hearts", "10H", "Ten of Hearts", "TH", "10 of hearts", "10♥", " th "].iter() {
+            assert_eq!(read(text), (Num(10), Hearts), "{}", text);
+        }
+        assert_eq!(read("11, Spades"), (Jack, Spades));
+        assert_eq!(read("13c"), (King, Clubs));
+        assert_eq!(read("Queen of Diamonds"), (Queen, Diamonds));
+        assert_eq!(read("AC"), (Ace, Clubs));
+        assert_eq!(read("XD"), (Joker, Diamonds));
+        assert_eq!(read("Black Joker"), (Joker, Spades));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!("".parse::<Card>(), Err(CardParseError::Empty));
+        assert_eq!("Eleven of Hearts".parse::<Card>(), Err(CardParseError::BadRank("Eleven".to_string())));
+        assert_eq!("10, Stars".parse::<Card>(), Err(CardParseError::BadSuit("Stars".to_string())));
+        assert_eq!("1ZH".parse::<Card>(), Err(CardParseError::BadRank("1Z".to_string())));
+        assert_eq!("H".parse::<Card>(), Err(CardParseError::BadFormat("H".to_string())));
+        assert_eq!("Green Joker".parse::<Card>(), Err(CardParseError::BadFormat("Green Joker".to_string())));
+        assert_eq!("Queen Hearts".parse::<Card>(), Err(CardParseError::BadFormat("Queen Hearts".to_string())));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_old_parsers() {
+        let card = Card::parse_card("12, Hearts".to_string());
+        assert_eq!((card.rank, card.suit), (Queen, Hearts));
+        assert_eq!(Card::get_suit(" Clubs ".to_string()), Clubs);
+    }
+
+    #[test]
+    fn test_round_trips() {
+        let deck = DeckSpec { decks: 1, lowest: 2, jokers: 2 }.build();
+        for &card in deck.iter() {
+            let written = [card.to_string(), Card::card_to_string(card), render::code(card),
+                           Style::Symbols.card(card)];
+            for text in written.iter() {
+                assert_eq!(read(text), (card.rank, card.suit), "{}", text);
+            }
+        }
+    }
+}
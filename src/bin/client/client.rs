@@ -1,80 +1,387 @@
 // MIT License
 // Copyright (c) 2018 Cole Phares
-// Last Modified: 6/10/2018
+// Last Modified: 10/18/2026
 // Client side for online card game Egyptian RatScrew
 
 extern crate card;
 extern crate termion;
+use card::Card;
+use card::config::{self, ClientConfig, ConfigError};
+use card::engine::{Event, Penalty, TakeReason};
+use card::protocol::{self, ClientMessage, Feature, ServerMessage};
+use card::render::Style;
+use std::env;
 use std::net::TcpStream;
+use std::process;
 use std::io::{BufReader, Write, BufRead, stdin, stdout};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 use termion::event::Key;
 use termion::raw::IntoRawMode;
 use termion::input::TermRead;
 use std::thread;
+
+mod screen;
+mod viewer;
 //use std::time::Duration;
 
+/// Messages kept for the message pane
+const MAX_MESSAGES: usize = 100;
+
+/// What the client knows about the table it sits at
+struct Table {
+    seat: usize,
+    names: Vec<String>,
+    // Cards every player holds in their hand and in their side pile
+    hands: Vec<(usize, usize)>,
+    // Size of the pile and the cards on top of it, the top card last
+    pile_size: usize,
+    pile: Vec<Card>,
+    // Player answering a face card and how many cards they have left
+    challenge: Option<(usize, u32)>,
+    // What happened so far, the newest message last
+    messages: Vec<String>,
+    your_turn: bool,
+    over: bool,
+    // How cards are written out
+    style: Style,
+}
+
+impl Table {
+    fn new(seat: usize, names: Vec<String>, style: Style) -> Self {
+        Table {
+            seat,
+            names,
+            hands: Vec::new(),
+            pile_size: 0,
+            pile: Vec::new(),
+            challenge: None,
+            messages: Vec::new(),
+            your_turn: false,
+            over: false,
+            style,
+        }
+    }
+
+    /// Adds a line to the message pane
+    fn say(&mut self, text: &str) {
+        self.messages.push(text.to_string());
+        if self.messages.len() > MAX_MESSAGES {
+            self.messages.remove(0);
+        }
+    }
+
+    // Name the player knows a seat by
+    fn name(&self, player: usize) -> String {
+        if player == self.seat {
+            "You".to_string()
+        } else {
+            self.names.get(player).cloned().unwrap_or_else(|| format!("Player {}", player + 1))
+        }
+    }
+
+    /// Takes in a message from the server, returns false once the game
+    /// is over
+    fn show(&mut self, message: ServerMessage) -> bool {
+        let keep_going = self.update(message);
+        if !keep_going {
+            self.over = true;
+            self.your_turn = false;
+        }
+        keep_going
+    }
+
+    fn update(&mut self, message: ServerMessage) -> bool {
+        let text = match message {
+            ServerMessage::Welcome { version, .. } => {
+                if version < protocol::OLDEST_VERSION || version > protocol::VERSION {
+                    self.say(&format!("The server wants protocol version {}, this client speaks {} to {}. \
+                                       Please update the client.",
+                                      version, protocol::OLDEST_VERSION, protocol::VERSION));
+                    return false;
+                }
+                return true;
+            }
+            ServerMessage::Rejected { reason } => {
+                self.say(&format!("The server turned us away: {}", reason));
+                return false;
+            }
+            ServerMessage::Lobby { players } => {
+                format!("{} player(s) at the table. The host presses c to deal.", players)
+            }
+            ServerMessage::TablesFull => "Every table is busy. Press c again once a game ends.".to_string(),
+            ServerMessage::Seated { seat, first, names } => {
+                self.seat = seat;
+                self.names = names;
+                let text = format!("You are {}. {} player(s) at the table.",
+                                   self.names[seat], self.names.len());
+                self.say(&text);
+                if first == seat {
+                    "You go first!".to_string()
+                } else {
+                    format!("{} goes first!", self.name(first))
+                }
+            }
+            ServerMessage::YourTurn => {
+                self.your_turn = true;
+                "Your turn! Press c to play card".to_string()
+            }
+            ServerMessage::Pile { size, top } => {
+                self.pile_size = size;
+                self.pile = top;
+                return true;
+            }
+            ServerMessage::Hands { counts } => {
+                self.hands = counts;
+                return true;
+            }
+            ServerMessage::CantSlap => "You can't slap this turn!".to_string(),
+            ServerMessage::Event(event) => {
+                self.follow(&event);
+                if let Some(text) = self.describe(&event) {
+                    self.say(&text);
+                }
+                // The game is over once someone wins it
+                return !matches!(event, Event::GameWon { .. });
+            }
+            ServerMessage::Notice(text) => text,
+        };
+        self.say(&text);
+        true
+    }
+
+    // Keeps track of the pile and the challenge as the game goes on
+    fn follow(&mut self, event: &Event) {
+        match *event {
+            Event::CardPlayed { player, .. } => {
+                if player == self.seat {
+                    self.your_turn = false;
+                }
+                if let Some((challenged, remaining)) = self.challenge {
+                    if challenged == player {
+                        self.challenge = Some((challenged, remaining.saturating_sub(1)));
+                    }
+                }
+            }
+            Event::ChallengeStarted { challenged, count, .. } => self.challenge = Some((challenged, count)),
+            Event::PileTaken { .. } => {
+                self.challenge = None;
+                self.pile_size = 0;
+                self.pile.clear();
+            }
+            _ => {},
+        }
+    }
+
+    // Describes something that happened in the game
+    fn describe(&self, event: &Event) -> Option<String> {
+        let text = match *event {
+            Event::CardPlayed { player, .. } => {
+                if player == self.seat {
+                    return None;
+                }
+                format!("{}'s turn!", self.name(player))
+            }
+            Event::ChallengeStarted { challenged, card, count, .. } => {
+                if challenged == self.seat {
+                    format!("Play {} card(s) to answer the {:?}!", count, card.rank)
+                } else {
+                    format!("{} must play {} card(s) to answer the {:?}!",
+                            self.name(challenged), count, card.rank)
+                }
+            }
+            Event::Slapped { ref found, ref cards, .. } => {
+                // Show which combination was slapped
+                let cards: Vec<String> = cards.iter().map(|&c| self.style.card(c)).collect();
+                format!("{}: {}", found.pattern, cards.join(" / "))
+            }
+            Event::FalseSlap { player, penalty } => {
+                match penalty {
+                    // The pile changing hands is reported on its own
+                    Penalty::GivePile => return None,
+                    Penalty::Burn(count) => format!("No combination. {} must burn {} card(s)!",
+                                                    self.name(player), count),
+                    Penalty::LockOut => format!("No combination. {} can't slap until the next card is played!",
+                                                self.name(player)),
+                }
+            }
+            Event::Burned { player, ref cards } => {
+                let cards: Vec<String> = cards.iter().map(|&c| self.style.card(c)).collect();
+                format!("{} burned {} to the bottom of the pile.", self.name(player), cards.join(" / "))
+            }
+            Event::PileTaken { player, reason, .. } => {
+                let found = match reason {
+                    TakeReason::Slap => "Combination found.",
+                    TakeReason::FalseSlap => "No combination.",
+                    TakeReason::Challenge => "No face card.",
+                    TakeReason::LastCard => "Every card is down.",
+                };
+                if player == self.seat {
+                    format!("{} You won the pot!!", found)
+                } else {
+                    format!("{} {} gets the pot!!", found, self.name(player))
+                }
+            }
+            Event::OutOfCards { player } => {
+                if player == self.seat {
+                    "You are out of cards! Slap the pile to get back in.".to_string()
+                } else {
+                    format!("{} is out of cards!", self.name(player))
+                }
+            }
+            Event::GameWon { player } => {
+                if player == self.seat {
+                    "Congratulations!! You won the game!!".to_string()
+                } else {
+                    format!("Oh, too bad. {} won the game!!", self.name(player))
+                }
+            }
+            Event::PlayerQuit { player } => format!("{} quit!", self.name(player)),
+        };
+        Some(text)
+    }
+}
+
+/// Sends a message to the server
+fn send<T>(writer: &mut T, message: ClientMessage) where T: Write {
+    write!(writer, "{}\r\n", message.encode()).ok();
+    writer.flush().unwrap();
+}
+
+/// What the terminal can show, for the server to know
+fn features(style: Style) -> Vec<Feature> {
+    let mut features = Vec::new();
+    if style.colour() {
+        features.push(Feature::Colour);
+    }
+    if style.unicode() {
+        features.push(Feature::Unicode);
+    }
+    features
+}
+
 /// Players game control
-fn play_game(stream: &TcpStream) {
+fn play_game(stream: &TcpStream, name: &str, style: Style) {
     let network_reader = stream.try_clone().unwrap();
     let mut key_writer = stream.try_clone().unwrap();
 
+    // Say hello before anything else
+    let hello = ClientMessage::Hello {
+        version: protocol::VERSION,
+        features: features(style),
+        name: name.to_string(),
+    };
+    send(&mut key_writer, hello);
+
+    // Until the cards are dealt, c asks the server to deal
+    let dealt = Arc::new(AtomicBool::new(false));
+    let seated = dealt.clone();
+
+    // Both threads draw the table, so they take turns with it
+    let table = Arc::new(Mutex::new(Table::new(0, Vec::new(), style)));
+    let network_table = table.clone();
+
+    // Set up standard output for drawing the whole screen
+    let mut out = stdout().into_raw_mode().unwrap();
+    screen::enter(&mut out);
+    screen::draw(&mut out, &table.lock().unwrap());
+
     // Thread to control key events
     let key_handler = thread::spawn(move || {
-        // Set up standard input for event handling
         let stdin = stdin();
-        let mut _stdout = stdout().into_raw_mode().unwrap();
-        
         for c in stdin.keys() {
             match c.unwrap() {
                 // Press Space bar to slap the pile
-                Key::Char(' ') => {
-                    write!(key_writer, "space\r\n").ok();
-                    key_writer.flush().unwrap();
-                },
-                // Press 'c' to play a card
+                Key::Char(' ') => send(&mut key_writer, ClientMessage::Slap),
+                // Press 'c' to deal, then to play a card
                 Key::Char('c') => {
-                    write!(key_writer, "c\r\n").ok();
-                    key_writer.flush().unwrap();
+                    if dealt.load(Ordering::SeqCst) {
+                        send(&mut key_writer, ClientMessage::PlayCard);
+                    } else {
+                        send(&mut key_writer, ClientMessage::Deal);
+                    }
                 }
                 // Press 'q' to quit at anytime
                 Key::Char('q') => {
-                    println!();
-                    println!("Thank you for playing!\r\n");
-                    write!(key_writer, "q").ok();
-                    key_writer.flush().unwrap();
-                    
+                    send(&mut key_writer, ClientMessage::Quit);
                     return;
                 },
                 Key::Char('\0') => {},
-                _ => println!("Invalid key pressed\r\n")
+                _ => {
+                    let mut table = table.lock().unwrap();
+                    table.say("Invalid key pressed");
+                    screen::draw(&mut stdout(), &table);
+                }
             }
         }
     });
 
     // Thread to handle messages from the server
     thread::spawn(move || {
-        let mut reader = BufReader::new(&network_reader);
-        loop {
-            let mut message = String::new();
-            match BufRead::read_line(&mut reader, &mut message) {
-                Ok(n) => {
-                    if n != 0 {
-                        print!("{}", message);
-                        if message.contains("wins") {
-                            return;
-                        }
-                    }
-                },
-                _ => return,
+        let reader = BufReader::new(&network_reader);
+        for line in reader.lines() {
+            let message = match line {
+                Ok(line) => ServerMessage::decode(&line),
+                Err(_) => return,
+            };
+            // Newer servers may send messages this client doesn't know,
+            // those are skipped
+            if let Ok(message) = message {
+                if let ServerMessage::Seated { .. } = message {
+                    seated.store(true, Ordering::SeqCst);
+                }
+                let mut table = network_table.lock().unwrap();
+                let going = table.show(message);
+                screen::draw(&mut stdout(), &table);
+                if !going {
+                    return;
+                }
             }
         }
     });
 
     // Call key event here to allow for key events and messages from server
     key_handler.join().unwrap();
+    screen::leave(&mut out);
+    drop(out);
+    println!("Thank you for playing!");
 }
 
+/// Command-line help
+const USAGE: &str = "Usage: client [options]
+
+Options:
+    --config FILE     read settings from a TOML file, flags win over it
+    --host ADDRESS    address of the server (127.0.0.1)
+    --port PORT       port the server listens on (24794)
+    --replay FILE     play back a game log written by the server
+    --cards STYLE     how cards are written: words, codes, symbols, colour
+                      or unicode (picked to suit the terminal)";
+
 fn main() {
+    let config: ClientConfig = match config::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            println!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let style = config.cards.unwrap_or_else(Style::detect);
+
+    // Watch a recorded game instead of playing one
+    if let Some(ref path) = config.replay {
+        if let Err(err) = viewer::view(path, style) {
+            println!("{}", err);
+            process::exit(1);
+        }
+        return;
+    }
+
     let mut input = String::new();
     let mut valid_input = false;
 
@@ -82,7 +389,7 @@ fn main() {
         // Print welcome prompt
         println!("Welcome to Egyptian Ratscrew!!\nPress p to play:");
         stdin().read_line(&mut input).ok();
-        let prompt = input.bytes().nth(0);
+        let prompt = input.as_bytes().first().copied();
         match prompt.unwrap() as char {
             'p' => valid_input = true,
             _ => println!("Incorrect character! Please try again.")
@@ -91,10 +398,15 @@ fn main() {
         println!("Press 'Q' at anytime to quit");
         println!();
     }
+
+    // Ask for the name the other players will know us by
+    println!("What's your name? Leave it empty to be given one:");
+    let mut name = String::new();
+    stdin().read_line(&mut name).ok();
     
     // Connect to the server
-    if let Ok(stream) = TcpStream::connect("127.0.0.1:24794") {
-        play_game(&stream);
+    if let Ok(stream) = TcpStream::connect((config.host.as_ref(), config.port)) {
+        play_game(&stream, name.trim(), style);
     } else {
         println!("Couldn't connect to server...");
     }
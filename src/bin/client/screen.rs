@@ -0,0 +1,138 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Draws the table the client sits at across the whole terminal
+
+use std::io::Write;
+use termion::{self, clear, cursor, style};
+use termion::screen::{ToAlternateScreen, ToMainScreen};
+use super::Table;
+
+/// Size assumed when the terminal won't say
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// Columns between the left edges of overlapping cards
+const CARD_STEP: usize = 4;
+
+const KEYS: &str = " c deal/play   space slap   q quit";
+
+/// Switches to a screen of our own so the game doesn't scroll the
+/// terminal away
+pub fn enter<W: Write>(out: &mut W) {
+    write!(out, "{}{}", ToAlternateScreen, cursor::Hide).ok();
+    out.flush().ok();
+}
+
+/// Gives the terminal back the way it was
+pub fn leave<W: Write>(out: &mut W) {
+    write!(out, "{}{}{}", style::Reset, cursor::Show, ToMainScreen).ok();
+    out.flush().ok();
+}
+
+/// Draws the whole screen
+pub fn draw<W: Write>(out: &mut W, table: &Table) {
+    let (width, height) = termion::terminal_size().unwrap_or(DEFAULT_SIZE);
+    let (width, height) = (width as usize, height as usize);
+
+    let mut lines = Vec::new();
+    lines.extend(table_lines(table));
+    lines.push(String::new());
+    lines.extend(pile_lines(table));
+    lines.push(challenge_line(table));
+    lines.push(String::new());
+
+    // The messages get what's left, leaving room for the two bars
+    let room = height.saturating_sub(lines.len() + 3);
+    lines.push("Messages".to_string());
+    let messages = &table.messages;
+    for message in messages[messages.len().saturating_sub(room)..].iter() {
+        lines.push(message.clone());
+    }
+
+    write!(out, "{}{}", clear::All, cursor::Goto(1, 1)).ok();
+    title_bar(out, table, width);
+    for (row, line) in lines.iter().take(height.saturating_sub(2)).enumerate() {
+        write!(out, "{}{}", cursor::Goto(1, row as u16 + 2), fit(line, width)).ok();
+    }
+    status_bar(out, table, width, height);
+    out.flush().ok();
+}
+
+fn title_bar<W: Write>(out: &mut W, table: &Table, width: usize) {
+    let title = " Egyptian RatScrew";
+    let you = match table.names.get(table.seat) {
+        Some(name) => format!("You are {} ", name),
+        None => "Waiting for the deal ".to_string(),
+    };
+    write!(out, "{}{}{}", style::Invert, bar(title, &you, width), style::Reset).ok();
+}
+
+fn status_bar<W: Write>(out: &mut W, table: &Table, width: usize, height: usize) {
+    let state = if table.over {
+        "Game over, press q "
+    } else if table.your_turn {
+        "Your turn! "
+    } else {
+        ""
+    };
+    write!(out, "{}{}{}{}",
+           cursor::Goto(1, height as u16), style::Invert, bar(KEYS, state, width), style::Reset).ok();
+}
+
+/// Text on the left and the right of a line the width of the screen
+fn bar(left: &str, right: &str, width: usize) -> String {
+    let gap = width.saturating_sub(left.chars().count() + right.chars().count());
+    fit(&format!("{}{}{}", left, " ".repeat(gap), right), width)
+}
+
+/// Cuts a line down to the width of the screen. Colour codes take up no
+/// room and are always kept.
+fn fit(line: &str, width: usize) -> String {
+    let mut fitted = String::new();
+    let mut shown = 0;
+    let mut in_code = false;
+    for c in line.chars() {
+        if c == '\x1b' {
+            in_code = true;
+        }
+        if in_code {
+            fitted.push(c);
+            in_code = c != 'm';
+        } else if shown < width {
+            fitted.push(c);
+            shown += 1;
+        }
+    }
+    fitted
+}
+
+/// A line for every player with the cards they hold
+fn table_lines(table: &Table) -> Vec<String> {
+    (0..table.names.len()).map(|player| {
+        let marker = match table.challenge {
+            Some((challenged, _)) if challenged == player => ">",
+            _ => " ",
+        };
+        let cards = match table.hands.get(player) {
+            Some(&(hand, side)) => format!("{:>3} in hand {:>3} on the side", hand, side),
+            None => String::new(),
+        };
+        format!(" {} {:<24} {}", marker, table.name(player), cards)
+    }).collect()
+}
+
+/// The top cards of the pile drawn overlapping, the top card in full
+fn pile_lines(table: &Table) -> Vec<String> {
+    let mut lines = vec![format!(" Pile: {} card(s)", table.pile_size)];
+    lines.extend(table.style.spread(&table.pile, CARD_STEP).into_iter().map(|line| format!("   {}", line)));
+    lines
+}
+
+fn challenge_line(table: &Table) -> String {
+    match table.challenge {
+        Some((challenged, remaining)) => {
+            format!(" {} must find a face card in {} more card(s)", table.name(challenged), remaining)
+        }
+        None => String::new(),
+    }
+}
@@ -0,0 +1,234 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Plays back a recorded Egyptian RatScrew game in the terminal
+
+use card::engine::{Action, Event, GameState};
+use card::render::Style;
+use card::replay::{GameLog, Step};
+use std::fs::File;
+use std::io::{BufReader, Write, stdout};
+use std::thread;
+use std::time::{Duration, Instant};
+use termion::{self, clear, cursor};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use super::Table;
+
+/// How fast the game can be played back, compared to how it was played
+const SPEEDS: [f64; 7] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// Speed the viewer starts at, as an index into SPEEDS
+const NORMAL_SPEED: usize = 2;
+
+/// How often the viewer checks the keys and the clock
+const TICK: u64 = 20;
+
+/// Cards of the pile shown, the top card last
+const PILE_SHOWN: usize = 8;
+
+/// Where a recorded game is being played back
+struct Viewer {
+    path: String,
+    seed: u64,
+    // The game as dealt, before anyone played
+    dealt: GameState,
+    steps: Vec<Step>,
+    // Steps played so far, 0 for none
+    shown: usize,
+    // Milliseconds since the deal in the recorded game
+    clock: f64,
+    playing: bool,
+    speed: usize,
+    table: Table,
+}
+
+impl Viewer {
+    /// The game as it stands after the steps shown so far
+    fn game(&self) -> &GameState {
+        match self.shown {
+            0 => &self.dealt,
+            shown => &self.steps[shown - 1].game,
+        }
+    }
+
+    /// Plays the steps that are due by the clock
+    fn advance(&mut self, elapsed: Duration) {
+        if !self.playing {
+            return;
+        }
+        let millis = elapsed.as_secs() as f64 * 1000.0 + elapsed.subsec_nanos() as f64 / 1_000_000.0;
+        self.clock += millis * SPEEDS[self.speed];
+        while self.shown < self.steps.len() && self.steps[self.shown].at as f64 <= self.clock {
+            self.shown += 1;
+        }
+        if self.shown == self.steps.len() {
+            self.playing = false;
+        }
+    }
+
+    /// Shows the step at `shown` and sets the clock to it
+    fn jump(&mut self, shown: usize) {
+        self.shown = shown.min(self.steps.len());
+        self.clock = match self.shown {
+            0 => 0.0,
+            shown => self.steps[shown - 1].at as f64,
+        };
+    }
+
+    /// Jumps to the next slap, whether it found a combination or not
+    fn next_slap(&mut self) {
+        let next = self.steps[self.shown..].iter().position(is_slap);
+        if let Some(offset) = next {
+            self.jump(self.shown + offset + 1);
+        }
+    }
+
+    /// Handles a key, returns false to stop the viewer
+    fn key(&mut self, key: Key) -> bool {
+        match key {
+            Key::Char(' ') => {
+                if self.shown == self.steps.len() {
+                    self.jump(0);
+                }
+                self.playing = !self.playing;
+            }
+            Key::Right | Key::Char('l') => {
+                self.playing = false;
+                let next = self.shown + 1;
+                self.jump(next);
+            }
+            Key::Left | Key::Char('h') => {
+                self.playing = false;
+                let previous = self.shown.saturating_sub(1);
+                self.jump(previous);
+            }
+            Key::Char('n') => self.next_slap(),
+            Key::Char('+') | Key::Char('=') => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Key::Char('-') => self.speed = self.speed.saturating_sub(1),
+            Key::Home => self.jump(0),
+            Key::End => self.jump(self.steps.len()),
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => return false,
+            _ => {},
+        }
+        true
+    }
+
+    /// Draws the whole screen
+    fn draw<W: Write>(&self, out: &mut W) {
+        let game = self.game();
+        let mut lines = Vec::new();
+        lines.push(format!("Replay of {}", self.path));
+        lines.push(format!("Seed {}   {}   step {} of {}   {}x   {}",
+                           self.seed, clock(self.clock as u64), self.shown, self.steps.len(),
+                           SPEEDS[self.speed],
+                           if self.playing { "playing" } else { "paused" }));
+        lines.push(String::new());
+
+        for player in 0..game.num_players() {
+            let marker = if !game.is_over() && player == game.turn() { ">" } else { " " };
+            let status = if game.has_left(player) {
+                "left".to_string()
+            } else {
+                format!("{} cards", game.player(player).card_count())
+            };
+            lines.push(format!("{} {:<24} {}", marker, self.table.name(player), status));
+        }
+        lines.push(String::new());
+
+        let pile = game.pile();
+        let top: Vec<String> = pile.top(PILE_SHOWN).iter()
+                                   .map(|&card| self.table.style.card(card))
+                                   .collect();
+        lines.push(format!("Pile ({} cards): {}", pile.len(), top.join(" / ")));
+        lines.push(String::new());
+
+        if self.shown == 0 {
+            lines.push(format!("The cards are dealt. {} goes first.", self.table.name(game.turn())));
+        } else {
+            let step = &self.steps[self.shown - 1];
+            for event in step.events.iter() {
+                let text = match *event {
+                    Event::CardPlayed { player, card } => Some(format!("{} played {}.", self.table.name(player), self.table.style.card(card))),
+                    ref event => self.table.describe(event),
+                };
+                if let Some(text) = text {
+                    lines.push(text);
+                }
+            }
+            // Late slaps lost to the last slap played, which may be
+            // a step or two back
+            let slapped = self.steps[..self.shown].iter()
+                              .rev()
+                              .find(|step| is_slap(step))
+                              .map_or(step.at, |step| step.at);
+            for &(at, player) in step.late.iter() {
+                lines.push(format!("{} slapped {} ms later, too late.",
+                                   self.table.name(player), at.saturating_sub(slapped)));
+            }
+        }
+        lines.push(String::new());
+        lines.push("space play/pause   left/right step   n next slap   +/- speed   \
+                    home/end start/end   q quit".to_string());
+
+        write!(out, "{}{}{}", clear::All, cursor::Goto(1, 1), lines.join("\r\n")).ok();
+        out.flush().ok();
+    }
+}
+
+fn is_slap(step: &Step) -> bool {
+    matches!(step.action, Action::Slap(_))
+}
+
+/// Minutes, seconds and milliseconds since the deal
+fn clock(at: u64) -> String {
+    format!("{}:{:02}.{:03}", at / 60_000, at / 1000 % 60, at % 1000)
+}
+
+/// Plays back the game logged in a file until the viewer quits
+pub fn view(path: &str, style: Style) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("Can't open {}: {}", path, err))?;
+    let log = GameLog::read(BufReader::new(file)).map_err(|err| format!("{}: {}", path, err))?;
+    let steps = log.steps().map_err(|err| format!("{} doesn't replay. {}", path, err))?;
+
+    let mut viewer = Viewer {
+        path: path.to_string(),
+        seed: log.header.seed,
+        dealt: log.header.deal(),
+        steps,
+        shown: 0,
+        clock: 0.0,
+        playing: true,
+        speed: NORMAL_SPEED,
+        // Nobody in a replay is "you"
+        table: Table::new(log.header.names.len(), log.header.names.clone(), style),
+    };
+
+    let mut out = stdout().into_raw_mode().map_err(|err| err.to_string())?;
+    write!(out, "{}", cursor::Hide).ok();
+    let mut keys = termion::async_stdin().keys();
+    let mut last = Instant::now();
+    let mut drawn = None;
+    'viewing: loop {
+        for key in keys.by_ref().flatten() {
+            if !viewer.key(key) {
+                break 'viewing;
+            }
+            drawn = None;
+        }
+        let now = Instant::now();
+        viewer.advance(now - last);
+        last = now;
+
+        // Redraw when anything on the screen changed
+        let state = (viewer.shown, viewer.clock as u64 / 100, viewer.playing, viewer.speed);
+        if drawn != Some(state) {
+            viewer.draw(&mut out);
+            drawn = Some(state);
+        }
+        thread::sleep(Duration::from_millis(TICK));
+    }
+    write!(out, "{}\r\n", cursor::Show).ok();
+    Ok(())
+}
@@ -0,0 +1,580 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Settings for the Egyptian RatScrew server and client
+
+//! Settings for the server and the client.
+//!
+//! Settings start out at their defaults, are then read from the TOML file
+//! named with `--config`, and last from the other command-line flags, so a
+//! flag always wins over the file. Settings inside a TOML table are named
+//! `table.key`, for example `deck.jokers`.
+
+use std::error;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::time::Duration;
+use toml::Value;
+use ai::Difficulty;
+use deck::DeckSpec;
+use engine::Penalty;
+use render::Style;
+use rules::{RuleSet, SlapPattern};
+
+/// Port the server listens on unless told otherwise
+pub const DEFAULT_PORT: u16 = 24794;
+
+/// Longest time a setting in seconds can be given, an hour
+pub const MAX_SECONDS: f64 = 3600.0;
+
+/// Reasons the settings can't be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The user asked for help instead of a game
+    Help,
+    /// The config file couldn't be read, with the reason why
+    File(String, String),
+    /// A command-line flag the program doesn't know
+    UnknownFlag(String),
+    /// A command-line flag was given without its value
+    MissingValue(String),
+    /// A setting the program doesn't know
+    UnknownSetting(String),
+    /// A setting was given a value it can't take
+    BadValue(String, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Help => write!(f, "Help requested"),
+            ConfigError::File(ref path, ref reason) => write!(f, "Can't read {}: {}", path, reason),
+            ConfigError::UnknownFlag(ref flag) => write!(f, "Unknown flag {}", flag),
+            ConfigError::MissingValue(ref flag) => write!(f, "Flag {} needs a value", flag),
+            ConfigError::UnknownSetting(ref key) => write!(f, "Unknown setting {}", key),
+            ConfigError::BadValue(ref key, ref value) => write!(f, "Bad value {} for {}", value, key),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+/// Settings that can be read from a config file and the command line
+pub trait Settings: Default {
+    // Changes one setting
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError>;
+
+    // Setting changed by a command-line flag, named without its dashes
+    fn flag(name: &str) -> Option<&'static str>;
+}
+
+/// Settings for the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Address to listen on
+    pub host: String,
+    pub port: u16,
+    /// Combinations that can be slapped
+    pub rules: RuleSet,
+    /// Cards dealt at every table
+    pub deck: DeckSpec,
+    /// What a player pays for slapping a pile without a combination
+    pub penalty: Penalty,
+    /// How good the computer player is when a player sits alone
+    pub difficulty: Difficulty,
+    /// Names of the bots seated at every table along with the players
+    pub bots: Vec<String>,
+    /// Programs that play as bots, by the name they are seated under,
+    /// with their arguments
+    pub commands: Vec<(String, String)>,
+    /// How long the pile stays open for slaps after a card hits it
+    pub slap_window: Duration,
+    /// How long a player gets to play a card before the server plays it
+    /// for them, None to wait as long as it takes
+    pub turn_timeout: Option<Duration>,
+    /// Seed for every game, None for a new seed every game
+    pub seed: Option<u64>,
+    /// Folder every game is logged in, None to not log games
+    pub logs: Option<String>,
+    /// Most games the server runs at once
+    pub max_games: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: DEFAULT_PORT,
+            rules: RuleSet::classic(),
+            deck: DeckSpec::standard(),
+            penalty: Penalty::default(),
+            difficulty: Difficulty::default(),
+            bots: Vec::new(),
+            commands: Vec::new(),
+            slap_window: Duration::from_secs(3),
+            turn_timeout: None,
+            seed: None,
+            logs: Some("logs".to_string()),
+            max_games: 16,
+        }
+    }
+}
+
+impl Settings for ServerConfig {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "host" => self.host = value.to_string(),
+            "port" => self.port = parse(key, value)?,
+            "slap_window" => self.slap_window = window(key, value)?,
+            "turn_timeout" => self.turn_timeout = seconds(key, value)?,
+            "penalty" => self.penalty = penalty(key, value)?,
+            "seed" => self.seed = Some(parse(key, value)?),
+            "logs" if value.trim().is_empty() => self.logs = None,
+            "logs" => self.logs = Some(value.to_string()),
+            "max_games" => {
+                self.max_games = parse(key, value)?;
+                if self.max_games < 1 {
+                    return Err(bad_value(key, value));
+                }
+            }
+            "difficulty" => {
+                self.difficulty = Difficulty::from_name(value).ok_or_else(|| bad_value(key, value))?;
+            }
+            "bots" => self.bots = names(value),
+            // Flags name the bot and its program together
+            "commands" => {
+                let mut parts = value.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(name), Some(command)) if !name.trim().is_empty() => {
+                        self.set(&format!("commands.{}", name.trim()), command)?;
+                    }
+                    _ => return Err(bad_value(key, value)),
+                }
+            }
+            _ if key.starts_with("commands.") => {
+                let name = &key["commands.".len()..];
+                self.commands.retain(|(known, _)| known != name);
+                self.commands.push((name.to_string(), value.trim().to_string()));
+            }
+            _ => return set_table(&mut self.rules, &mut self.deck, key, value),
+        }
+        Ok(())
+    }
+
+    fn flag(name: &str) -> Option<&'static str> {
+        let key = match name {
+            "host" => "host",
+            "port" => "port",
+            "difficulty" => "difficulty",
+            "bots" => "bots",
+            "command" => "commands",
+            "slap-window" => "slap_window",
+            "turn-timeout" => "turn_timeout",
+            "penalty" => "penalty",
+            "seed" => "seed",
+            "logs" => "logs",
+            "max-games" => "max_games",
+            _ => return table_flag(name),
+        };
+        Some(key)
+    }
+}
+
+/// Settings for games played by bots alone
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulateConfig {
+    /// Number of games to play
+    pub games: u32,
+    /// Seed for the random numbers, the same seed plays the same games
+    pub seed: u64,
+    /// Combinations that can be slapped
+    pub rules: RuleSet,
+    /// Cards dealt for every game
+    pub deck: DeckSpec,
+    /// What a bot pays for slapping a pile without a combination
+    pub penalty: Penalty,
+    /// Names of the bots at the table, in seat order
+    pub bots: Vec<String>,
+    /// How long the pile stays open for slaps after a card hits it
+    pub slap_window: Duration,
+}
+
+impl Default for SimulateConfig {
+    fn default() -> Self {
+        SimulateConfig {
+            games: 1000,
+            seed: 1,
+            rules: RuleSet::classic(),
+            deck: DeckSpec::standard(),
+            penalty: Penalty::default(),
+            bots: vec!["normal".to_string(), "normal".to_string()],
+            slap_window: Duration::from_secs(3),
+        }
+    }
+}
+
+impl Settings for SimulateConfig {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "games" => self.games = parse(key, value)?,
+            "seed" => self.seed = parse(key, value)?,
+            "bots" => self.bots = names(value),
+            "slap_window" => self.slap_window = window(key, value)?,
+            "penalty" => self.penalty = penalty(key, value)?,
+            _ => return set_table(&mut self.rules, &mut self.deck, key, value),
+        }
+        Ok(())
+    }
+
+    fn flag(name: &str) -> Option<&'static str> {
+        let key = match name {
+            "games" => "games",
+            "seed" => "seed",
+            "bots" => "bots",
+            "slap-window" => "slap_window",
+            "penalty" => "penalty",
+            _ => return table_flag(name),
+        };
+        Some(key)
+    }
+}
+
+/// Settings for the client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Address of the server
+    pub host: String,
+    pub port: u16,
+    /// Game log to play back instead of joining a game
+    pub replay: Option<String>,
+    /// How cards are written out, None to go by what the terminal can show
+    pub cards: Option<Style>,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            host: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+            replay: None,
+            cards: None,
+        }
+    }
+}
+
+impl Settings for ClientConfig {
+    fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        match key {
+            "host" => self.host = value.to_string(),
+            "port" => self.port = parse(key, value)?,
+            "replay" => self.replay = Some(value.to_string()),
+            "cards" => {
+                self.cards = Some(Style::from_name(value).ok_or_else(|| bad_value(key, value))?);
+            }
+            _ => return Err(ConfigError::UnknownSetting(key.to_string())),
+        }
+        Ok(())
+    }
+
+    fn flag(name: &str) -> Option<&'static str> {
+        match name {
+            "host" => Some("host"),
+            "port" => Some("port"),
+            "replay" => Some("replay"),
+            "cards" => Some("cards"),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the settings from the command-line arguments, not counting the
+/// program name
+pub fn from_args<T, I>(args: I) -> Result<T, ConfigError>
+    where T: Settings, I: IntoIterator<Item = String>
+{
+    let mut config = T::default();
+    let mut flags = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Err(ConfigError::Help);
+        }
+        if !arg.starts_with("--") {
+            return Err(ConfigError::UnknownFlag(arg));
+        }
+        let value = args.next().ok_or_else(|| ConfigError::MissingValue(arg.clone()))?;
+        if arg == "--config" {
+            let mut text = String::new();
+            File::open(&value)
+                .and_then(|mut file| file.read_to_string(&mut text))
+                .map_err(|err| ConfigError::File(value.clone(), err.to_string()))?;
+            from_toml(&mut config, &text).map_err(|err| match err {
+                ConfigError::File(_, reason) => ConfigError::File(value.clone(), reason),
+                err => err,
+            })?;
+        } else {
+            flags.push((arg, value));
+        }
+    }
+
+    // Flags go last so they win over the file
+    for (arg, value) in flags {
+        let key = T::flag(&arg[2..]).ok_or_else(|| ConfigError::UnknownFlag(arg.clone()))?;
+        config.set(key, &value)?;
+    }
+    Ok(config)
+}
+
+/// Changes the settings named in a TOML document
+pub fn from_toml<T: Settings>(config: &mut T, text: &str) -> Result<(), ConfigError> {
+    let document = text.parse::<Value>()
+                       .map_err(|err| ConfigError::File(String::new(), err.to_string()))?;
+    let mut settings = Vec::new();
+    flatten("", &document, &mut settings)?;
+
+    // A preset replaces a whole table, so it goes before the settings
+    // that change it
+    settings.sort_by_key(|(key, _)| !key.ends_with(".preset"));
+    for (key, value) in settings {
+        config.set(&key, &value)?;
+    }
+    Ok(())
+}
+
+/// Lists the settings in a TOML value, naming the ones inside tables
+/// `table.key`
+fn flatten(key: &str, value: &Value, settings: &mut Vec<(String, String)>) -> Result<(), ConfigError> {
+    let text = match *value {
+        Value::Table(ref table) => {
+            for (name, value) in table.iter() {
+                let key = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                flatten(&key, value, settings)?;
+            }
+            return Ok(());
+        }
+        Value::String(ref text) => text.clone(),
+        Value::Integer(n) => n.to_string(),
+        Value::Float(n) => n.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Array(ref values) => {
+            let mut items = Vec::new();
+            for value in values.iter() {
+                match *value {
+                    Value::String(ref text) => items.push(text.clone()),
+                    _ => return Err(bad_value(key, &value.to_string())),
+                }
+            }
+            items.join(",")
+        }
+        Value::Datetime(ref date) => return Err(bad_value(key, &date.to_string())),
+    };
+    settings.push((key.to_string(), text));
+    Ok(())
+}
+
+/// Changes a setting for the cards and rules at a table, the `rules` and
+/// `deck` tables
+fn set_table(rules: &mut RuleSet, deck: &mut DeckSpec, key: &str, value: &str) -> Result<(), ConfigError> {
+    match key {
+        "rules.preset" => {
+            *rules = match value {
+                "classic" => RuleSet::classic(),
+                "all" => RuleSet::all(),
+                _ => return Err(bad_value(key, value)),
+            };
+        }
+        "rules.with" | "rules.without" => {
+            for name in value.split(',').map(|name| name.trim()) {
+                let pattern = SlapPattern::from_name(name).ok_or_else(|| bad_value(key, name))?;
+                rules.set(pattern, key == "rules.with");
+            }
+        }
+        "deck.decks" | "deck.lowest" | "deck.jokers" => {
+            let number = parse(key, value)?;
+            let mut changed = *deck;
+            match key {
+                "deck.decks" => changed.decks = number,
+                "deck.lowest" => changed.lowest = number,
+                _ => changed.jokers = number,
+            }
+            // Each number is checked on its own, so they can be given in
+            // any order
+            if !changed.is_valid() {
+                return Err(bad_value(key, value));
+            }
+            *deck = changed;
+        }
+        _ if key.starts_with("rules.") => {
+            let pattern = SlapPattern::from_name(&key["rules.".len()..])
+                .ok_or_else(|| ConfigError::UnknownSetting(key.to_string()))?;
+            rules.set(pattern, parse(key, value)?);
+        }
+        _ => return Err(ConfigError::UnknownSetting(key.to_string())),
+    }
+    Ok(())
+}
+
+/// Setting changed by a flag for the cards and rules at a table
+fn table_flag(name: &str) -> Option<&'static str> {
+    let key = match name {
+        "rules" => "rules.preset",
+        "with" => "rules.with",
+        "without" => "rules.without",
+        "decks" => "deck.decks",
+        "lowest" => "deck.lowest",
+        "jokers" => "deck.jokers",
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn bad_value(key: &str, value: &str) -> ConfigError {
+    ConfigError::BadValue(key.to_string(), value.to_string())
+}
+
+fn parse<T: ::std::str::FromStr>(key: &str, value: &str) -> Result<T, ConfigError> {
+    value.trim().parse().map_err(|_| bad_value(key, value))
+}
+
+/// Reads a number of seconds up to `MAX_SECONDS`, 0 meaning none
+fn seconds(key: &str, value: &str) -> Result<Option<Duration>, ConfigError> {
+    let seconds: f64 = parse(key, value)?;
+    if !seconds.is_finite() || !(0.0..=MAX_SECONDS).contains(&seconds) {
+        return Err(bad_value(key, value));
+    }
+    if seconds == 0.0 {
+        return Ok(None);
+    }
+    Ok(Some(Duration::from_millis((seconds * 1000.0) as u64)))
+}
+
+/// Reads a penalty by its name
+fn penalty(key: &str, value: &str) -> Result<Penalty, ConfigError> {
+    Penalty::from_name(value.trim()).ok_or_else(|| bad_value(key, value))
+}
+
+/// Reads how long the pile stays open, which can't be none
+fn window(key: &str, value: &str) -> Result<Duration, ConfigError> {
+    seconds(key, value)?.ok_or_else(|| bad_value(key, value))
+}
+
+/// Splits a list of names separated by commas
+fn names(value: &str) -> Vec<String> {
+    value.split(',')
+         .map(|name| name.trim().to_string())
+         .filter(|name| !name.is_empty())
+         .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config: ServerConfig = from_args(Vec::new()).unwrap();
+
+        assert_eq!(config, ServerConfig::default());
+        assert_eq!(config.port, DEFAULT_PORT);
+        assert_eq!(config.rules, RuleSet::classic());
+        assert_eq!(config.logs, Some("logs".to_string()));
+    }
+
+    #[test]
+    fn test_flags() {
+        let config: ServerConfig = from_args(args("--port 4000 --with marriage,tens --without pair \
+                                                   --decks 2 --lowest 7 --slap-window 1.5 \
+                                                   --turn-timeout 20 --difficulty expert \
+                                                   --bots hard,expert \
+                                                   --command mine=python3 --seed 12 \
+                                                   --max-games 4")).unwrap();
+
+        assert_eq!(config.port, 4000);
+        assert!(config.rules.marriage && config.rules.tens && !config.rules.pair);
+        assert_eq!(config.deck, DeckSpec::standard().decks(2).stripped(7));
+        assert_eq!(config.slap_window, Duration::from_millis(1500));
+        assert_eq!(config.turn_timeout, Some(Duration::from_secs(20)));
+        assert_eq!(config.difficulty, Difficulty::Expert);
+        assert_eq!(config.bots, vec!["hard".to_string(), "expert".to_string()]);
+        assert_eq!(config.commands, vec![("mine".to_string(), "python3".to_string())]);
+        assert_eq!(config.seed, Some(12));
+        assert_eq!(config.max_games, 4);
+
+        let client: ClientConfig = from_args(args("--host example.com --replay logs/1.log \
+                                                   --cards codes")).unwrap();
+        assert_eq!(client.host, "example.com");
+        assert_eq!(client.replay, Some("logs/1.log".to_string()));
+        assert_eq!(client.cards, Some(Style::Codes));
+
+        let simulate: SimulateConfig = from_args(args("--games 50 --seed 7 --bots easy,hard,expert \
+                                                       --jokers 2 --penalty lock-out")).unwrap();
+        assert_eq!((simulate.games, simulate.seed), (50, 7));
+        assert_eq!(simulate.bots.len(), 3);
+        assert_eq!(simulate.deck.jokers, 2);
+        assert_eq!(simulate.penalty, Penalty::LockOut);
+    }
+
+    #[test]
+    fn test_toml() {
+        let mut config = ServerConfig::default();
+        from_toml(&mut config, "port = 5000\n\
+                                logs = \"\"\n\
+                                penalty = \"burn:2\"\n\
+                                [rules]\n\
+                                run = false\n\
+                                preset = \"all\"\n\
+                                [deck]\n\
+                                jokers = 2\n\
+                                [commands]\n\
+                                mine = \"python3 bots/mine.py\"\n").unwrap();
+
+        assert_eq!(config.port, 5000);
+        assert_eq!(config.logs, None);
+        assert_eq!(config.penalty, Penalty::Burn(2));
+        assert!(!config.rules.run && config.rules.divorce);
+        assert_eq!(config.deck.jokers, 2);
+        assert_eq!(config.commands, vec![("mine".to_string(), "python3 bots/mine.py".to_string())]);
+    }
+
+    #[test]
+    fn test_bad_settings() {
+        assert_eq!(from_args::<ServerConfig, _>(args("--port")),
+                   Err(ConfigError::MissingValue("--port".to_string())));
+        assert_eq!(from_args::<ServerConfig, _>(args("--colour red")),
+                   Err(ConfigError::UnknownFlag("--colour".to_string())));
+        assert_eq!(from_args::<ServerConfig, _>(args("--lowest 11")),
+                   Err(ConfigError::BadValue("deck.lowest".to_string(), "11".to_string())));
+        assert_eq!(from_args::<SimulateConfig, _>(args("--penalty burn:x")),
+                   Err(ConfigError::BadValue("penalty".to_string(), "burn:x".to_string())));
+        assert_eq!(from_args::<ServerConfig, _>(args("--max-games 0")),
+                   Err(ConfigError::BadValue("max_games".to_string(), "0".to_string())));
+        assert_eq!(from_args::<SimulateConfig, _>(args("--jokers 4000000000")),
+                   Err(ConfigError::BadValue("deck.jokers".to_string(), "4000000000".to_string())));
+        assert_eq!(from_args::<SimulateConfig, _>(args("--decks 0")),
+                   Err(ConfigError::BadValue("deck.decks".to_string(), "0".to_string())));
+        assert_eq!(from_args::<ServerConfig, _>(args("--decks 101")),
+                   Err(ConfigError::BadValue("deck.decks".to_string(), "101".to_string())));
+        assert_eq!(from_args::<ClientConfig, _>(args("--rules all")),
+                   Err(ConfigError::UnknownFlag("--rules".to_string())));
+        assert_eq!(from_args::<ServerConfig, _>(args("--help")), Err(ConfigError::Help));
+    }
+
+    #[test]
+    fn test_bad_seconds() {
+        for &value in ["-1", "inf", "NaN", "1e300", "3600.5"].iter() {
+            assert_eq!(from_args::<ServerConfig, _>(args(&format!("--turn-timeout {}", value))),
+                       Err(ConfigError::BadValue("turn_timeout".to_string(), value.to_string())));
+        }
+        assert_eq!(from_args::<SimulateConfig, _>(args("--slap-window 0")),
+                   Err(ConfigError::BadValue("slap_window".to_string(), "0".to_string())));
+
+        let config: ServerConfig = from_args(args("--slap-window 3600 --turn-timeout 0")).unwrap();
+        assert_eq!(config.slap_window, Duration::from_secs(3600));
+        assert_eq!(config.turn_timeout, None);
+    }
+}
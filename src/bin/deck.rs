@@ -0,0 +1,431 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Decks, hands and piles for Egyptian RatScrew
+
+//! The stacks of cards a game is played with.
+//!
+//! `DeckSpec` says which cards make up a deck and builds it. A `Deck` is
+//! shuffled and dealt out into a `Hand` for every player. Players play from
+//! the top of their hand onto the `Pile` in the middle, and the piles they
+//! win go face down on their side pile, also a `Pile`, until their hand runs
+//! out and they pick it up.
+//!
+//! All three keep their cards bottom first, so the top card is always the
+//! last one in `cards()`. Iterating goes the other way, from the top down,
+//! the order the cards are dealt or looked at in.
+
+use rand::Rng;
+use std::iter::Rev;
+use std::ops::Range;
+use std::slice::Iter;
+use {Card, Rank::*, Suit::*};
+
+/// Most standard decks combined into one
+pub const MAX_DECKS: u32 = 100;
+
+/// Most jokers added to each deck
+pub const MAX_JOKERS: u32 = 100;
+
+/// Describes which cards make up the deck for a game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeckSpec {
+    /// Number of standard decks combined into one
+    pub decks: u32,
+    /// Lowest number card kept in each suit, stripped decks leave out
+    /// the cards below it
+    pub lowest: u32,
+    /// Jokers added to each deck, alternating red and black
+    pub jokers: u32,
+}
+
+impl DeckSpec {
+    /// A single standard 52 card deck
+    pub fn standard() -> Self {
+        DeckSpec {
+            decks: 1,
+            lowest: 2,
+            jokers: 0,
+        }
+    }
+
+    /// Combines several standard decks
+    pub fn decks(mut self, decks: u32) -> Self {
+        assert!(decks >= 1, "A game needs at least one deck");
+        assert!(decks <= MAX_DECKS, "{} decks is more than {}", decks, MAX_DECKS);
+        self.decks = decks;
+        self
+    }
+
+    /// Strips the number cards below `lowest` from every suit
+    /// for a shorter game
+    pub fn stripped(mut self, lowest: u32) -> Self {
+        assert!((2..=10).contains(&lowest), "Lowest card {} is not a number card", lowest);
+        self.lowest = lowest;
+        self
+    }
+
+    /// Adds jokers to every deck
+    pub fn jokers(mut self, jokers: u32) -> Self {
+        assert!(jokers <= MAX_JOKERS, "{} jokers is more than {}", jokers, MAX_JOKERS);
+        self.jokers = jokers;
+        self
+    }
+
+    /// Whether the builders could have made the spec. The fields can be
+    /// set without them, so specs read from elsewhere are checked with this.
+    pub fn is_valid(&self) -> bool {
+        self.decks >= 1 && self.decks <= MAX_DECKS && self.lowest >= 2 && self.lowest <= 10
+            && self.jokers <= MAX_JOKERS
+    }
+
+    /// Number of cards in the deck, or `usize::MAX` if there are more
+    /// than that
+    pub fn size(&self) -> usize {
+        let per_suit = self.numbers().len() + 4;
+        (per_suit * 4).saturating_add(self.jokers as usize)
+                      .saturating_mul(self.decks as usize)
+    }
+
+    // Number cards in each suit. The fields can be set without the
+    // builders, so a lowest card that isn't a number card is kept in range.
+    fn numbers(&self) -> Range<u32> {
+        self.lowest.clamp(2, 11)..11
+    }
+
+    /// Creates the deck, unshuffled
+    pub fn build(&self) -> Deck {
+        assert!(self.is_valid(), "Can't build a deck from {:?}", self);
+        let mut deck: Vec<Card> = Vec::with_capacity(self.size());
+        for _ in 0..self.decks {
+            for suit in [Hearts, Diamonds, Clubs, Spades].iter() {
+                for i in self.numbers() {
+                    deck.push(Card::new(Num(i), *suit));
+                }
+                for face in [Jack, Queen, King, Ace].iter() {
+                    deck.push(Card::new(*face, *suit));
+                }
+            }
+            for i in 0..self.jokers {
+                let suit = if i % 2 == 0 { Hearts } else { Spades };
+                deck.push(Card::new(Joker, suit));
+            }
+        }
+        Deck::from(deck)
+    }
+}
+
+impl Default for DeckSpec {
+    fn default() -> Self {
+        DeckSpec::standard()
+    }
+}
+
+/// Cards face down waiting to be dealt
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deck {
+    // Bottom card first
+    cards: Vec<Card>,
+}
+
+impl Deck {
+    /// Number of cards in the deck
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// The cards from the bottom up, the top card last
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// The cards from the top down
+    pub fn iter(&self) -> Rev<Iter<'_, Card>> {
+        self.cards.iter().rev()
+    }
+
+    /// Shuffles the deck a random number of times between 5 and 15
+    pub fn shuffle_with<R: Rng>(&mut self, rng: &mut R) {
+        let times: usize = rng.gen_range(5, 15);
+        for _ in 0..times {
+            rng.shuffle(&mut self.cards);
+        }
+    }
+
+    /// Lifts `count` cards off the top and puts them underneath the rest
+    pub fn cut(&mut self, count: usize) {
+        let count = count.min(self.cards.len());
+        let bottom = self.cards.len() - count;
+        self.cards.rotate_left(bottom);
+    }
+
+    /// Deals the whole deck out one card at a time from the top, starting
+    /// with the first hand. Every card dealt goes on top of the hand.
+    pub fn deal(mut self, players: usize) -> Vec<Hand> {
+        assert!(players >= 1, "Cards can't be dealt to nobody");
+        let mut hands: Vec<Hand> = (0..players).map(|_| Hand::new()).collect();
+        let mut seat = 0;
+        while let Some(card) = self.cards.pop() {
+            hands[seat].cards.push(card);
+            seat = (seat + 1) % players;
+        }
+        hands
+    }
+}
+
+/// Cards given bottom first
+impl From<Vec<Card>> for Deck {
+    fn from(cards: Vec<Card>) -> Self {
+        Deck { cards }
+    }
+}
+
+/// Cards a player holds face down and plays from the top of
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Hand {
+    // Bottom card first
+    cards: Vec<Card>,
+}
+
+impl Hand {
+    pub fn new() -> Self {
+        Hand { cards: Vec::new() }
+    }
+
+    /// Number of cards in the hand
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// The cards from the bottom up, the top card last
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// The cards from the top down, the order they're played in
+    pub fn iter(&self) -> Rev<Iter<'_, Card>> {
+        self.cards.iter().rev()
+    }
+
+    /// Takes the top card off to play it
+    pub fn take_top(&mut self) -> Option<Card> {
+        self.cards.pop()
+    }
+
+    /// Slides cards in underneath the hand, keeping their order, so they
+    /// come up after every card already in it
+    pub fn put_under(&mut self, cards: Vec<Card>) {
+        self.cards.splice(0..0, cards);
+    }
+
+    /// Picks up a whole deck underneath the hand
+    pub fn pick_up(&mut self, deck: Deck) {
+        self.put_under(deck.cards);
+    }
+
+    /// Empties the hand, giving back its cards bottom first
+    pub fn take_all(&mut self) -> Vec<Card> {
+        self.cards.split_off(0)
+    }
+}
+
+/// Cards given bottom first
+impl From<Vec<Card>> for Hand {
+    fn from(cards: Vec<Card>) -> Self {
+        Hand { cards }
+    }
+}
+
+/// A stack of cards laid one on top of the other, such as the pile in the
+/// middle of the table or a player's side pile
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Pile {
+    // Bottom card first
+    cards: Vec<Card>,
+}
+
+impl Pile {
+    pub fn new() -> Self {
+        Pile { cards: Vec::new() }
+    }
+
+    /// Number of cards in the pile
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+
+    /// The cards from the bottom up, the top card last
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// The cards from the top down
+    pub fn iter(&self) -> Rev<Iter<'_, Card>> {
+        self.cards.iter().rev()
+    }
+
+    /// The top card, if there is one
+    pub fn top_card(&self) -> Option<Card> {
+        self.cards.last().cloned()
+    }
+
+    /// Up to `count` cards from the top of the pile, the top card last
+    pub fn top(&self, count: usize) -> &[Card] {
+        &self.cards[self.cards.len() - count.min(self.cards.len())..]
+    }
+
+    /// Lays a card on top of the pile
+    pub fn lay(&mut self, card: Card) {
+        self.cards.push(card);
+    }
+
+    /// Lays cards on top of the pile in order, the last one ending on top
+    pub fn lay_all(&mut self, cards: Vec<Card>) {
+        self.cards.extend(cards);
+    }
+
+    /// Slides a card in underneath the pile as a penalty
+    pub fn burn_to_bottom(&mut self, card: Card) {
+        self.cards.insert(0, card);
+    }
+
+    /// Slides cards in underneath the pile, keeping their order
+    pub fn put_under(&mut self, cards: Vec<Card>) {
+        self.cards.splice(0..0, cards);
+    }
+
+    /// Picks up the whole pile, giving back its cards bottom first
+    pub fn take_all(&mut self) -> Vec<Card> {
+        self.cards.split_off(0)
+    }
+}
+
+/// Cards given bottom first
+impl From<Vec<Card>> for Pile {
+    fn from(cards: Vec<Card>) -> Self {
+        Pile { cards }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_deck() {
+        let deck = DeckSpec::standard().build();
+
+        assert_eq!(deck.len(), 52);
+        assert_eq!(DeckSpec::standard().size(), 52);
+        assert_eq!(deck.iter().filter(|c| c.suit == Spades).count(), 13);
+    }
+
+    #[test]
+    fn test_combined_decks_with_jokers() {
+        let spec = DeckSpec::standard().decks(2).jokers(2);
+        let deck = spec.build();
+
+        assert_eq!(deck.len(), 108);
+        assert_eq!(spec.size(), 108);
+        assert_eq!(deck.iter().filter(|c| c.rank == Joker).count(), 4);
+    }
+
+    #[test]
+    fn test_stripped_deck() {
+        let spec = DeckSpec::standard().stripped(7);
+        let deck = spec.build();
+
+        assert_eq!(deck.len(), 32);
+        assert_eq!(spec.size(), 32);
+        assert!(deck.iter().all(|c| c.rank.value() >= 7 || c.rank == Ace));
+    }
+
+    #[test]
+    fn test_size_out_of_range() {
+        // Only the face cards are left above 10
+        let spec = DeckSpec { decks: 1, lowest: 12, jokers: 0 };
+        assert_eq!(spec.size(), 16);
+        assert!(!spec.is_valid());
+        assert_eq!(DeckSpec { decks: 1, lowest: 0, jokers: 0 }.size(), 52);
+
+        let huge = DeckSpec { decks: u32::MAX, lowest: 2, jokers: u32::MAX };
+        assert!(huge.size() > u32::MAX as usize);
+        assert!(!huge.is_valid());
+        assert!(!DeckSpec { jokers: MAX_JOKERS + 1, ..DeckSpec::standard() }.is_valid());
+        assert!(DeckSpec::standard().decks(MAX_DECKS).jokers(MAX_JOKERS).is_valid());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't build a deck")]
+    fn test_build_too_many_decks() {
+        DeckSpec { decks: MAX_DECKS + 1, ..DeckSpec::standard() }.build();
+    }
+
+    #[test]
+    #[should_panic(expected = "Cards can't be dealt to nobody")]
+    fn test_deal_to_nobody() {
+        DeckSpec::standard().build().deal(0);
+    }
+
+    fn numbers(values: &[u32]) -> Vec<Card> {
+        values.iter().map(|&n| Card::new(Num(n), Hearts)).collect()
+    }
+
+    fn values<'a, I: Iterator<Item = &'a Card>>(cards: I) -> Vec<u32> {
+        cards.map(|c| c.rank.value()).collect()
+    }
+
+    #[test]
+    fn test_deal_and_cut() {
+        let mut deck = Deck::from(numbers(&[2, 3, 4, 5, 6]));
+        assert_eq!(values(deck.iter()), vec![6, 5, 4, 3, 2]);
+
+        deck.cut(2);
+        assert_eq!(values(deck.iter()), vec![4, 3, 2, 6, 5]);
+
+        // One card at a time from the top, each on top of its hand
+        let hands = deck.deal(2);
+        assert_eq!(values(hands[0].iter()), vec![5, 2, 4]);
+        assert_eq!(values(hands[1].iter()), vec![6, 3]);
+    }
+
+    #[test]
+    fn test_hand_plays_from_top() {
+        let mut hand = Hand::from(numbers(&[2, 3]));
+        hand.put_under(numbers(&[7, 8]));
+        assert_eq!(hand.take_top().map(|c| c.rank), Some(Num(3)));
+        assert_eq!(values(hand.iter()), vec![2, 8, 7]);
+        assert_eq!(hand.take_all().len(), 3);
+        assert_eq!(hand.take_top(), None);
+    }
+
+    #[test]
+    fn test_pile_top_and_bottom() {
+        let mut pile = Pile::new();
+        pile.lay(Card::new(Num(5), Hearts));
+        pile.lay_all(numbers(&[6, 7]));
+        pile.burn_to_bottom(Card::new(Num(2), Hearts));
+        pile.put_under(numbers(&[9, 10]));
+
+        assert_eq!(values(pile.iter()), vec![7, 6, 5, 2, 10, 9]);
+        assert_eq!(values(pile.top(2).iter()), vec![6, 7]);
+        assert_eq!(pile.top(10).len(), 6);
+        assert_eq!(pile.top_card().map(|c| c.rank), Some(Num(7)));
+
+        assert_eq!(values(pile.take_all().iter()), vec![9, 10, 2, 5, 6, 7]);
+        assert!(pile.is_empty());
+        assert_eq!(pile.top_card(), None);
+    }
+}
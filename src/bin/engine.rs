@@ -0,0 +1,784 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Game engine for Egyptian RatScrew
+
+//! The rules of the game, free of any networking or terminal IO.
+//!
+//! A `GameState` is driven by feeding it `Action`s, and answers each one
+//! with the `Event`s it caused. Front ends decide when players act and how
+//! events are shown; the engine decides what the actions mean.
+
+use std::error;
+use std::fmt;
+use super::{Card, Deck, GameRng, Hand, Pile, seeded};
+use rand::Rng;
+use rules::{RuleSet, SlapMatch};
+use Rank::*;
+
+/// Most players that fit around a table
+pub const MAX_PLAYERS: usize = 8;
+
+/// Something a player does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Lay the top card of the player's hand on the pile
+    PlayCard(usize),
+    /// Slap the pile
+    Slap(usize),
+    /// Pick up a pile the player has a claim on
+    Collect(usize),
+    /// Leave the game, the player's cards go to the bottom of the pile
+    Quit(usize),
+}
+
+/// Why a player took the pile
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TakeReason {
+    /// The player slapped a combination
+    Slap,
+    /// Another player slapped without a combination
+    FalseSlap,
+    /// The player won a face-card challenge
+    Challenge,
+    /// Every card was on the pile and the player laid the last one
+    LastCard,
+}
+
+/// What a player pays for slapping a pile without a combination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Penalty {
+    /// Burn this many cards from the player's hand to the bottom of the pile
+    Burn(u32),
+    /// Give the pile to the next player
+    GivePile,
+    /// Keep the player from slapping until another card has been played
+    LockOut,
+}
+
+impl Penalty {
+    /// Name of the penalty the way settings, logs and the protocol write
+    /// it: "burn:2", "give-pile" or "lock-out"
+    pub fn name(self) -> String {
+        match self {
+            Penalty::Burn(count) => format!("burn:{}", count),
+            Penalty::GivePile => "give-pile".to_string(),
+            Penalty::LockOut => "lock-out".to_string(),
+        }
+    }
+
+    /// Finds a penalty by name
+    pub fn from_name(name: &str) -> Option<Penalty> {
+        match name {
+            "give-pile" => Some(Penalty::GivePile),
+            "lock-out" => Some(Penalty::LockOut),
+            _ if name.starts_with("burn:") => name["burn:".len()..].parse().ok().map(Penalty::Burn),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Penalty {
+    fn default() -> Self {
+        Penalty::Burn(1)
+    }
+}
+
+/// Something that happened in the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// A card was laid on the pile
+    CardPlayed { player: usize, card: Card },
+    /// A face card was laid and the challenged player has to answer it
+    ChallengeStarted { challenger: usize, challenged: usize, card: Card, count: u32 },
+    /// A player slapped a combination, `cards` are the cards it is made of
+    Slapped { player: usize, found: SlapMatch, cards: Vec<Card> },
+    /// A player slapped a pile without a combination
+    FalseSlap { player: usize, penalty: Penalty },
+    /// A player burned cards to the bottom of the pile
+    Burned { player: usize, cards: Vec<Card> },
+    /// A player moved the pile to their side pile
+    PileTaken { player: usize, cards: usize, reason: TakeReason },
+    /// A player had no cards to play and sat out their turn
+    OutOfCards { player: usize },
+    /// A player holds every card
+    GameWon { player: usize },
+    /// A player left the game and put their cards under the pile
+    PlayerQuit { player: usize },
+}
+
+/// Reasons the engine refuses an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionError {
+    /// The game has already ended
+    GameOver,
+    /// There is no player with that index
+    UnknownPlayer(usize),
+    /// The player has left the game
+    NotPlaying(usize),
+    /// The player tried to play out of turn
+    NotYourTurn(usize),
+    /// The pile has to be collected before play goes on
+    PileClaimed(usize),
+    /// The player has no claim on the pile
+    NoClaim(usize),
+    /// The player is locked out from slapping
+    LockedOut(usize),
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ActionError::GameOver => write!(f, "The game is over"),
+            ActionError::UnknownPlayer(p) => write!(f, "There is no player {}", p),
+            ActionError::NotPlaying(p) => write!(f, "Player {} has left the game", p),
+            ActionError::NotYourTurn(p) => write!(f, "It isn't player {}'s turn", p),
+            ActionError::PileClaimed(p) => write!(f, "Player {} has to collect the pile first", p),
+            ActionError::NoClaim(p) => write!(f, "Player {} has no claim on the pile", p),
+            ActionError::LockedOut(p) => write!(f, "Player {} can't slap this turn", p),
+        }
+    }
+}
+
+impl error::Error for ActionError {}
+
+/// Contains a players hand and side pile
+#[derive(Debug, Clone)]
+pub struct PlayerState {
+    hand: Hand,
+    side_pile: Pile,
+}
+
+impl PlayerState {
+    fn new(hand: Hand) -> Self {
+        PlayerState {
+            hand,
+            side_pile: Pile::new(),
+        }
+    }
+
+    /// Cards the player plays from
+    pub fn hand(&self) -> &Hand {
+        &self.hand
+    }
+
+    /// Cards the player has won but not yet picked up
+    pub fn side_pile(&self) -> &Pile {
+        &self.side_pile
+    }
+
+    /// Number of cards the player holds in their hand and side pile
+    pub fn card_count(&self) -> usize {
+        self.hand.len() + self.side_pile.len()
+    }
+
+    /// Moves the side pile into the hand once the hand is empty
+    fn pick_up_side_pile(&mut self, rng: &mut GameRng) {
+        let mut side_pile = Deck::from(self.side_pile.take_all());
+        side_pile.shuffle_with(rng);
+        self.hand.pick_up(side_pile);
+    }
+}
+
+/// An outstanding face-card challenge
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Challenge {
+    /// Player who laid the face card
+    pub challenger: usize,
+    /// Cards the challenged player still has to lay down
+    pub remaining: u32,
+}
+
+/// A player's right to pick up the pile once nobody slaps it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Claim {
+    pub player: usize,
+    pub reason: TakeReason,
+}
+
+/// Number of cards the next player has to play in answer to a face card,
+/// or None if the card doesn't start a challenge
+pub fn challenge_count(card: Card) -> Option<u32> {
+    match card.rank {
+        Jack => Some(1),
+        Queen => Some(2),
+        King => Some(3),
+        Ace => Some(4),
+        Num(_) | Joker => None,
+    }
+}
+
+/// The complete state of one game
+#[derive(Debug, Clone)]
+pub struct GameState {
+    players: Vec<PlayerState>,
+    pile: Pile,
+    turn: usize,
+    challenge: Option<Challenge>,
+    claim: Option<Claim>,
+    rules: RuleSet,
+    penalty: Penalty,
+    lockouts: Vec<u32>,
+    left: Vec<bool>,
+    total: usize,
+    winner: Option<usize>,
+    over: bool,
+    // Shuffles the side piles players pick up
+    rng: GameRng,
+}
+
+impl GameState {
+    /// Deals the deck out to the players one card at a time and gives
+    /// the first turn to `first`
+    pub fn new(deck: Deck, players: usize, first: usize) -> GameState {
+        assert!(players >= 2, "A game needs at least two players");
+        assert!(first < players, "First player {} is not at the table", first);
+
+        let total = deck.len();
+        let states = deck.deal(players).into_iter().map(PlayerState::new).collect();
+
+        GameState {
+            players: states,
+            pile: Pile::new(),
+            turn: first,
+            challenge: None,
+            claim: None,
+            rules: RuleSet::default(),
+            penalty: Penalty::default(),
+            lockouts: vec![0; players],
+            left: vec![false; players],
+            total,
+            winner: None,
+            over: false,
+            rng: seeded(0),
+        }
+    }
+
+    /// Shuffles the deck, picks who goes first and deals, all with the
+    /// random numbers given. The game keeps drawing from numbers seeded
+    /// by them, so the same numbers play the same game.
+    pub fn shuffled(mut deck: Deck, players: usize, rng: &mut GameRng) -> GameState {
+        deck.shuffle_with(rng);
+        let first = rng.gen_range(0, players);
+        GameState::new(deck, players, first).with_seed(rng.gen())
+    }
+
+    /// Shuffles side piles with random numbers started from the seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = seeded(seed);
+        self
+    }
+
+    /// Uses a different set of slap rules than the default one
+    pub fn with_rules(mut self, rules: RuleSet) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Slap rules in play
+    pub fn rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    /// Uses a different penalty for false slaps than the default one
+    pub fn with_penalty(mut self, penalty: Penalty) -> Self {
+        self.penalty = penalty;
+        self
+    }
+
+    /// Penalty for false slaps
+    pub fn penalty(&self) -> Penalty {
+        self.penalty
+    }
+
+    /// Determines if the player is allowed to slap the pile
+    pub fn can_slap(&self, player: usize) -> bool {
+        self.lockouts[player] == 0 && !self.left[player]
+    }
+
+    /// Determines if the player has left the game
+    pub fn has_left(&self, player: usize) -> bool {
+        self.left[player]
+    }
+
+    /// Number of players at the table
+    pub fn num_players(&self) -> usize {
+        self.players.len()
+    }
+
+    /// Hand and side pile of a player
+    pub fn player(&self, player: usize) -> &PlayerState {
+        &self.players[player]
+    }
+
+    /// The pile in the middle
+    pub fn pile(&self) -> &Pile {
+        &self.pile
+    }
+
+    /// Player who plays the next card
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// The face-card challenge being answered, if any
+    pub fn challenge(&self) -> Option<Challenge> {
+        self.challenge
+    }
+
+    /// Player waiting to pick up the pile, if any
+    pub fn claim(&self) -> Option<Claim> {
+        self.claim
+    }
+
+    /// Number of cards in the game
+    pub fn total_cards(&self) -> usize {
+        self.total
+    }
+
+    /// Player holding every card, once the game is won
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Determines if the game has ended
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Determines if slapping the pile right now would win it
+    pub fn slappable(&self) -> bool {
+        self.rules.matches(self.pile.cards())
+    }
+
+    /// Applies an action and returns the events it caused
+    pub fn apply(&mut self, action: Action) -> Result<Vec<Event>, ActionError> {
+        if self.over {
+            return Err(ActionError::GameOver);
+        }
+        let player = match action {
+            Action::PlayCard(p) | Action::Slap(p) | Action::Collect(p) | Action::Quit(p) => p,
+        };
+        if player >= self.players.len() {
+            return Err(ActionError::UnknownPlayer(player));
+        }
+        if self.left[player] {
+            return Err(ActionError::NotPlaying(player));
+        }
+
+        let mut events = Vec::new();
+        match action {
+            Action::PlayCard(p) => self.play_card(p, &mut events)?,
+            Action::Slap(p) => self.slap(p, &mut events)?,
+            Action::Collect(p) => self.collect(p, &mut events)?,
+            Action::Quit(p) => self.quit(p, &mut events),
+        }
+        Ok(events)
+    }
+
+    /// Lays the top card of a players hand on the pile
+    fn play_card(&mut self, player: usize, events: &mut Vec<Event>) -> Result<(), ActionError> {
+        if let Some(claim) = self.claim {
+            return Err(ActionError::PileClaimed(claim.player));
+        }
+        if player != self.turn {
+            return Err(ActionError::NotYourTurn(player));
+        }
+
+        // Refill the hand from the side pile if it ran out
+        if self.players[player].hand.is_empty() {
+            self.players[player].pick_up_side_pile(&mut self.rng);
+        }
+        let card = self.players[player].hand.take_top().unwrap();
+        self.pile.lay(card);
+        events.push(Event::CardPlayed { player, card });
+
+        // Every card played wears down lock outs from false slaps
+        for lockout in self.lockouts.iter_mut() {
+            *lockout = lockout.saturating_sub(1);
+        }
+
+        if let Some(count) = challenge_count(card) {
+            // A face card starts a new challenge against the next player
+            self.challenge = Some(Challenge { challenger: player, remaining: count });
+            self.turn = self.next_player(player);
+            events.push(Event::ChallengeStarted {
+                challenger: player,
+                challenged: self.turn,
+                card,
+                count,
+            });
+        } else if let Some(mut challenge) = self.challenge.take() {
+            // The challenged player keeps playing until they either
+            // lay a face card or run out of tries
+            challenge.remaining -= 1;
+            if challenge.remaining == 0 {
+                self.claim = Some(Claim { player: challenge.challenger, reason: TakeReason::Challenge });
+                self.turn = challenge.challenger;
+            } else {
+                self.challenge = Some(challenge);
+            }
+        } else {
+            self.turn = self.next_player(player);
+        }
+
+        self.skip_empty_hands(player, events);
+        Ok(())
+    }
+
+    /// Passes the turn on from players who have no cards left to play
+    fn skip_empty_hands(&mut self, last: usize, events: &mut Vec<Event>) {
+        while self.claim.is_none() && self.players[self.turn].card_count() == 0 {
+            events.push(Event::OutOfCards { player: self.turn });
+
+            if let Some(challenge) = self.challenge.take() {
+                // The challenge can't be answered, so the challenger
+                // gets the pile
+                self.claim = Some(Claim { player: challenge.challenger, reason: TakeReason::Challenge });
+                self.turn = challenge.challenger;
+            } else if self.players.iter().all(|p| p.card_count() == 0) {
+                // Every card is in the pile, so it goes to whoever
+                // laid the last one
+                self.claim = Some(Claim { player: last, reason: TakeReason::LastCard });
+                self.turn = last;
+            } else {
+                self.turn = self.next_player(self.turn);
+            }
+        }
+    }
+
+    /// Slaps the pile, the pile goes to the slapper if there is a
+    /// combination and the slapper pays the penalty if there isn't
+    fn slap(&mut self, player: usize, events: &mut Vec<Event>) -> Result<(), ActionError> {
+        if !self.can_slap(player) {
+            return Err(ActionError::LockedOut(player));
+        }
+
+        if let Some(found) = self.rules.find(self.pile.cards()) {
+            let cards = found.cards(self.pile.cards());
+            events.push(Event::Slapped { player, found, cards });
+            self.take_pile(player, TakeReason::Slap, events);
+            return Ok(());
+        }
+
+        events.push(Event::FalseSlap { player, penalty: self.penalty });
+        match self.penalty {
+            Penalty::Burn(count) => self.burn(player, count, events),
+            Penalty::GivePile => {
+                if !self.pile.is_empty() {
+                    let next = self.next_player(player);
+                    self.take_pile(next, TakeReason::FalseSlap, events);
+                }
+            }
+            // Locked out through the next card, free again once the
+            // one after it is played
+            Penalty::LockOut => self.lockouts[player] = 2,
+        }
+        Ok(())
+    }
+
+    /// Moves cards from a players hand to the bottom of the pile
+    fn burn(&mut self, player: usize, count: u32, events: &mut Vec<Event>) {
+        let mut burned = Vec::new();
+        for _ in 0..count {
+            if self.players[player].hand.is_empty() {
+                self.players[player].pick_up_side_pile(&mut self.rng);
+            }
+            match self.players[player].hand.take_top() {
+                Some(card) => {
+                    self.pile.burn_to_bottom(card);
+                    burned.push(card);
+                }
+                None => break,
+            }
+        }
+        events.push(Event::Burned { player, cards: burned });
+
+        // The player may have burned their last card on their own turn
+        self.skip_empty_hands(player, events);
+    }
+
+    /// Picks up a pile the player has a claim on
+    fn collect(&mut self, player: usize, events: &mut Vec<Event>) -> Result<(), ActionError> {
+        match self.claim {
+            Some(claim) if claim.player == player => {
+                self.take_pile(player, claim.reason, events);
+                Ok(())
+            }
+            _ => Err(ActionError::NoClaim(player)),
+        }
+    }
+
+    /// Takes a player out of the game. Their cards go to the bottom of
+    /// the pile, so whoever wins still ends up holding every card.
+    fn quit(&mut self, player: usize, events: &mut Vec<Event>) {
+        self.left[player] = true;
+        let side_pile = self.players[player].side_pile.take_all();
+        self.pile.put_under(side_pile);
+        let hand = self.players[player].hand.take_all();
+        self.pile.put_under(hand);
+        events.push(Event::PlayerQuit { player });
+
+        // Claims and challenges of the player go with them
+        if self.claim.is_some_and(|claim| claim.player == player) {
+            self.claim = None;
+        }
+        if self.challenge.is_some_and(|challenge| challenge.challenger == player) {
+            self.challenge = None;
+        }
+
+        // The last player at the table wins
+        let playing: Vec<usize> = (0..self.players.len()).filter(|&p| !self.left[p]).collect();
+        if playing.len() == 1 {
+            self.winner = Some(playing[0]);
+            self.over = true;
+            events.push(Event::GameWon { player: playing[0] });
+            return;
+        }
+
+        if self.turn == player {
+            self.turn = self.next_player(player);
+        }
+        let turn = self.turn;
+        self.skip_empty_hands(turn, events);
+    }
+
+    /// Moves the pile to a players side pile, they start the next pile
+    fn take_pile(&mut self, player: usize, reason: TakeReason, events: &mut Vec<Event>) {
+        let cards = self.pile.len();
+        let pile = self.pile.take_all();
+        self.players[player].side_pile.lay_all(pile);
+        self.challenge = None;
+        self.claim = None;
+        self.turn = player;
+        events.push(Event::PileTaken { player, cards, reason });
+
+        // Determine if the player has won the game
+        if self.players[player].card_count() == self.total {
+            self.winner = Some(player);
+            self.over = true;
+            events.push(Event::GameWon { player });
+        }
+    }
+
+    /// Player sitting after `player` at the table who is still playing
+    fn next_player(&self, player: usize) -> usize {
+        let count = self.players.len();
+        (1..count).map(|i| (player + i) % count)
+                  .find(|&p| !self.left[p])
+                  .unwrap_or(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Rank, SlapPattern, Suit::*};
+
+    /// Builds a game where each player holds the given hand, the last
+    /// card of a hand is played first
+    fn game(hands: Vec<Vec<Card>>, first: usize) -> GameState {
+        let mut state = GameState::new(Deck::from(Vec::new()), hands.len(), first);
+        state.total = hands.iter().map(|h| h.len()).sum();
+        state.players = hands.into_iter()
+            .map(|hand| PlayerState::new(Hand::from(hand)))
+            .collect();
+        state
+    }
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Spades)
+    }
+
+    #[test]
+    fn test_deal_round_robin() {
+        let deck = vec![card(Num(2)), card(Num(3)), card(Num(4)), card(Num(5)), card(Num(6))];
+        let state = GameState::new(Deck::from(deck), 2, 1);
+
+        assert_eq!(state.player(0).hand().len(), 3);
+        assert_eq!(state.player(1).hand().len(), 2);
+        assert_eq!(state.turn(), 1);
+    }
+
+    #[test]
+    fn test_turns_alternate() {
+        let mut state = game(vec![vec![card(Num(2))], vec![card(Num(4))]], 0);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        assert_eq!(state.turn(), 1);
+        assert_eq!(state.apply(Action::PlayCard(0)), Err(ActionError::NotYourTurn(0)));
+    }
+
+    #[test]
+    fn test_unanswered_challenge() {
+        let mut state = game(vec![
+            vec![card(Num(2)), card(Queen)],
+            vec![card(Num(3)), card(Num(8)), card(Num(4))],
+        ], 0);
+
+        let events = state.apply(Action::PlayCard(0)).unwrap();
+        assert_eq!(events[1], Event::ChallengeStarted {
+            challenger: 0, challenged: 1, card: card(Queen), count: 2,
+        });
+        state.apply(Action::PlayCard(1)).unwrap();
+        assert_eq!(state.turn(), 1);
+        state.apply(Action::PlayCard(1)).unwrap();
+
+        assert_eq!(state.claim(), Some(Claim { player: 0, reason: TakeReason::Challenge }));
+        assert_eq!(state.apply(Action::PlayCard(0)), Err(ActionError::PileClaimed(0)));
+        state.apply(Action::Collect(0)).unwrap();
+        assert_eq!(state.player(0).card_count(), 4);
+        assert_eq!(state.turn(), 0);
+    }
+
+    #[test]
+    fn test_challenge_answered_with_face_card() {
+        let mut state = game(vec![
+            vec![card(Num(2)), card(Jack)],
+            vec![card(Num(3)), card(King)],
+        ], 0);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        state.apply(Action::PlayCard(1)).unwrap();
+
+        assert_eq!(state.challenge(), Some(Challenge { challenger: 1, remaining: 3 }));
+        assert_eq!(state.turn(), 0);
+    }
+
+    #[test]
+    fn test_slap_pair() {
+        let mut state = game(vec![vec![card(Num(2)), card(Num(7))], vec![card(Num(7))]], 0);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        state.apply(Action::PlayCard(1)).unwrap();
+        let events = state.apply(Action::Slap(1)).unwrap();
+
+        match events[0] {
+            Event::Slapped { player, ref found, ref cards } => {
+                assert_eq!(player, 1);
+                assert_eq!(found.pattern, SlapPattern::Pair);
+                assert_eq!(cards.len(), 2);
+            }
+            ref event => panic!("Unexpected event {:?}", event),
+        }
+        assert_eq!(events[1], Event::PileTaken { player: 1, cards: 2, reason: TakeReason::Slap });
+        assert_eq!(state.turn(), 1);
+    }
+
+    #[test]
+    fn test_false_slap_gives_pile() {
+        let mut state = game(vec![vec![card(Num(2)), card(Num(7))], vec![card(Num(5))]], 0)
+            .with_penalty(Penalty::GivePile);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        let events = state.apply(Action::Slap(1)).unwrap();
+
+        assert_eq!(events[0], Event::FalseSlap { player: 1, penalty: Penalty::GivePile });
+        assert_eq!(state.player(0).side_pile().len(), 1);
+        assert_eq!(state.turn(), 0);
+    }
+
+    #[test]
+    fn test_false_slap_burns_cards() {
+        let mut state = game(vec![vec![card(Num(7))], vec![card(Num(2)), card(Num(3)), card(Num(4))]], 0)
+            .with_penalty(Penalty::Burn(2));
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        let events = state.apply(Action::Slap(1)).unwrap();
+
+        assert_eq!(events[1], Event::Burned { player: 1, cards: vec![card(Num(4)), card(Num(3))] });
+        assert_eq!(state.pile().cards()[0].rank, Num(3));
+        assert_eq!(state.pile().len(), 3);
+        assert_eq!(state.player(1).card_count(), 1);
+        assert_eq!(state.turn(), 1);
+    }
+
+    #[test]
+    fn test_false_slap_locks_out() {
+        let mut state = game(vec![vec![card(Num(2)), card(Num(7)), card(Num(7))], vec![card(Num(5))]], 0)
+            .with_penalty(Penalty::LockOut);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        state.apply(Action::Slap(1)).unwrap();
+        assert!(!state.can_slap(1));
+
+        state.apply(Action::PlayCard(1)).unwrap();
+        assert_eq!(state.apply(Action::Slap(1)), Err(ActionError::LockedOut(1)));
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        assert!(state.can_slap(1));
+    }
+
+    #[test]
+    fn test_out_of_cards_keeps_playing() {
+        let mut state = game(vec![vec![card(Num(2)), card(Num(3)), card(Num(9))], vec![card(Num(5))]], 1);
+
+        state.apply(Action::PlayCard(1)).unwrap();
+        let events = state.apply(Action::PlayCard(0)).unwrap();
+
+        assert_eq!(events[1], Event::OutOfCards { player: 1 });
+        assert_eq!(state.turn(), 0);
+        assert!(!state.is_over());
+    }
+
+    #[test]
+    fn test_slap_back_in() {
+        let mut state = game(vec![vec![card(Num(2)), card(Num(5))], vec![card(Num(5))]], 1);
+
+        state.apply(Action::PlayCard(1)).unwrap();
+        state.apply(Action::PlayCard(0)).unwrap();
+        state.apply(Action::Slap(1)).unwrap();
+
+        assert_eq!(state.player(1).card_count(), 2);
+        assert_eq!(state.turn(), 1);
+    }
+
+    #[test]
+    fn test_quit_passes_turn_on() {
+        let mut state = game(vec![
+            vec![card(Num(2)), card(Num(3))],
+            vec![card(Num(4)), card(Num(5))],
+            vec![card(Num(6)), card(Num(7))],
+        ], 0);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        let events = state.apply(Action::Quit(1)).unwrap();
+
+        assert_eq!(events, vec![Event::PlayerQuit { player: 1 }]);
+        assert_eq!(state.turn(), 2);
+        assert_eq!(state.pile().len(), 3);
+        assert_eq!(state.pile().cards()[2].rank, Num(3));
+        assert_eq!(state.apply(Action::Slap(1)), Err(ActionError::NotPlaying(1)));
+
+        state.apply(Action::PlayCard(2)).unwrap();
+        assert_eq!(state.turn(), 0);
+
+        let events = state.apply(Action::Quit(2)).unwrap();
+        assert_eq!(events[1], Event::GameWon { player: 0 });
+    }
+
+    #[test]
+    fn test_win_by_challenge() {
+        let mut state = game(vec![vec![card(Ace)], vec![card(Num(4))]], 0);
+
+        state.apply(Action::PlayCard(0)).unwrap();
+        let events = state.apply(Action::PlayCard(1)).unwrap();
+        assert_eq!(events[1], Event::OutOfCards { player: 1 });
+
+        let events = state.apply(Action::Collect(0)).unwrap();
+        assert_eq!(events[1], Event::GameWon { player: 0 });
+        assert_eq!(state.winner(), Some(0));
+        assert_eq!(state.apply(Action::PlayCard(0)), Err(ActionError::GameOver));
+    }
+
+    #[test]
+    fn test_same_seed_same_deal() {
+        let deal = |seed| {
+            let state = GameState::shuffled(::make_deck(), 3, &mut ::seeded(seed));
+            (state.turn(), state.player(0).hand().cards().to_vec(), state.player(2).hand().cards().to_vec())
+        };
+
+        assert_eq!(deal(42), deal(42));
+        assert!(deal(42) != deal(43));
+    }
+}
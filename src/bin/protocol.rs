@@ -0,0 +1,605 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Messages passed between the server and its clients
+
+//! The wire protocol spoken between the server and its clients.
+//!
+//! Every message is one line of text. The first field names the message
+//! and the rest are separated by single spaces. Cards are written as short
+//! codes such as `7H`, `10S` or `QD`, jokers as `XH` or `XS`. Text fields
+//! escape spaces as `\s` and backslashes as `\\`. Fields past the ones a
+//! message needs are ignored, so later versions can add to a message
+//! without breaking older readers.
+//!
+//! A client opens with `hello`, giving the newest protocol version it
+//! speaks, the features it supports and the player's name. The server
+//! answers with `welcome`, naming the version and features both sides will
+//! use, or with `rejected` and a reason before hanging up.
+
+use std::error;
+use std::fmt;
+use std::str::Split;
+use super::Card;
+use engine::{Event, Penalty, TakeReason};
+use render;
+use rules::{SlapMatch, SlapPattern};
+
+/// Version of the protocol described by this module
+pub const VERSION: u32 = 3;
+
+/// Oldest version of the protocol this module still speaks. Version 2
+/// named slap patterns differently.
+pub const OLDEST_VERSION: u32 = 3;
+
+/// Something optional a client or server can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Feature {
+    /// Show cards in colour
+    Colour,
+    /// Show cards as unicode playing cards
+    Unicode,
+    /// Watch a table without sitting at it
+    Spectator,
+}
+
+impl Feature {
+    /// Every feature this version of the protocol knows
+    pub fn all() -> [Feature; 3] {
+        [Feature::Colour, Feature::Unicode, Feature::Spectator]
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Feature::Colour => "colour",
+            Feature::Unicode => "unicode",
+            Feature::Spectator => "spectator",
+        }
+    }
+}
+
+/// Reasons the server turns a client away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandshakeError {
+    /// The newest version the client speaks is older than the server's oldest
+    TooOld(u32),
+    /// The client sent something before saying hello
+    NoHello,
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HandshakeError::TooOld(version) => {
+                write!(f, "Protocol version {} is too old, the server needs version {} or newer. \
+                           Please update the client.", version, OLDEST_VERSION)
+            }
+            HandshakeError::NoHello => write!(f, "The client didn't say hello. Please update the client."),
+        }
+    }
+}
+
+impl error::Error for HandshakeError {}
+
+/// Agrees on how to talk to a client that said hello. The newest version
+/// both sides speak is used, and only the features both sides support.
+pub fn negotiate(version: u32, features: &[Feature], supported: &[Feature])
+                 -> Result<ServerMessage, HandshakeError> {
+    if version < OLDEST_VERSION {
+        return Err(HandshakeError::TooOld(version));
+    }
+    Ok(ServerMessage::Welcome {
+        version: version.min(VERSION),
+        features: features.iter().filter(|f| supported.contains(f)).cloned().collect(),
+    })
+}
+
+/// Something a client asks the server to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientMessage {
+    /// Opens the conversation with the newest version the client speaks,
+    /// the features it supports and the player's name
+    Hello { version: u32, features: Vec<Feature>, name: String },
+    /// Deal the cards, only the host of a table can
+    Deal,
+    /// Lay a card on the pile
+    PlayCard,
+    /// Slap the pile
+    Slap,
+    /// Leave the table
+    Quit,
+}
+
+/// Something the server tells a client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    /// Answers hello with the version and features to use from now on
+    Welcome { version: u32, features: Vec<Feature> },
+    /// Answers hello when the server won't talk to the client
+    Rejected { reason: String },
+    /// Number of players waiting at the table, the first of them deals
+    Lobby { players: usize },
+    /// The host can't deal because every table is busy
+    TablesFull,
+    /// The cards are dealt. `seat` is the client's own seat.
+    Seated { seat: usize, first: usize, names: Vec<String> },
+    /// It is the client's turn to play a card
+    YourTurn,
+    /// The pile changed, `top` holds up to five cards with the top card last
+    Pile { size: usize, top: Vec<Card> },
+    /// Cards every player holds by seat, in their hand and in their side
+    /// pile
+    Hands { counts: Vec<(usize, usize)> },
+    /// The client tried to slap while locked out
+    CantSlap,
+    /// Something happened in the game
+    Event(Event),
+    /// A message from the server that isn't part of the game
+    Notice(String),
+}
+
+/// Reasons a line can't be read as a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolError {
+    /// The line was empty
+    Empty,
+    /// The message isn't one this version of the protocol knows
+    UnknownMessage(String),
+    /// The message ended before this field
+    MissingField(&'static str),
+    /// The field couldn't be read
+    BadField(&'static str, String),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProtocolError::Empty => write!(f, "Empty message"),
+            ProtocolError::UnknownMessage(ref name) => write!(f, "Unknown message {}", name),
+            ProtocolError::MissingField(field) => write!(f, "Missing {}", field),
+            ProtocolError::BadField(field, ref value) => write!(f, "Bad {} {}", field, value),
+        }
+    }
+}
+
+impl error::Error for ProtocolError {}
+
+impl ClientMessage {
+    /// Writes the message as a line, without the line ending
+    pub fn encode(&self) -> String {
+        match *self {
+            ClientMessage::Hello { version, ref features, ref name } => {
+                format!("hello {} {} {}", version, encode_features(features), escape(name))
+            }
+            ClientMessage::Deal => "deal".to_string(),
+            ClientMessage::PlayCard => "play".to_string(),
+            ClientMessage::Slap => "slap".to_string(),
+            ClientMessage::Quit => "quit".to_string(),
+        }
+    }
+
+    /// Reads a message from a line
+    pub fn decode(line: &str) -> Result<ClientMessage, ProtocolError> {
+        let mut fields = Fields::new(line)?;
+        match fields.name {
+            "hello" => Ok(ClientMessage::Hello {
+                version: fields.number("version")?,
+                features: decode_features(fields.next("features")?),
+                // An empty name leaves nothing after the features
+                name: unescape(fields.rest().next().unwrap_or("")),
+            }),
+            "deal" => Ok(ClientMessage::Deal),
+            "play" => Ok(ClientMessage::PlayCard),
+            "slap" => Ok(ClientMessage::Slap),
+            "quit" => Ok(ClientMessage::Quit),
+            name => Err(fields.unknown(name)),
+        }
+    }
+}
+
+impl ServerMessage {
+    /// Writes the message as a line, without the line ending
+    pub fn encode(&self) -> String {
+        let mut line = Vec::new();
+        match *self {
+            ServerMessage::Welcome { version, ref features } => {
+                line.push("welcome".to_string());
+                line.push(version.to_string());
+                line.push(encode_features(features));
+            }
+            ServerMessage::Rejected { ref reason } => {
+                line.push("rejected".to_string());
+                line.push(escape(reason));
+            }
+            ServerMessage::Lobby { players } => {
+                line.push("lobby".to_string());
+                line.push(players.to_string());
+            }
+            ServerMessage::TablesFull => line.push("tables-full".to_string()),
+            ServerMessage::Seated { seat, first, ref names } => {
+                line.push("seated".to_string());
+                line.push(seat.to_string());
+                line.push(first.to_string());
+                line.extend(names.iter().map(|name| escape(name)));
+            }
+            ServerMessage::YourTurn => line.push("your-turn".to_string()),
+            ServerMessage::Pile { size, ref top } => {
+                line.push("pile".to_string());
+                line.push(size.to_string());
+                line.extend(top.iter().map(|&card| render::code(card)));
+            }
+            ServerMessage::Hands { ref counts } => {
+                line.push("hands".to_string());
+                line.extend(counts.iter().map(|&(hand, side)| format!("{},{}", hand, side)));
+            }
+            ServerMessage::CantSlap => line.push("cant-slap".to_string()),
+            ServerMessage::Event(ref event) => {
+                line.push("event".to_string());
+                encode_event(event, &mut line);
+            }
+            ServerMessage::Notice(ref text) => {
+                line.push("notice".to_string());
+                line.push(escape(text));
+            }
+        }
+        line.join(" ")
+    }
+
+    /// Reads a message from a line
+    pub fn decode(line: &str) -> Result<ServerMessage, ProtocolError> {
+        let mut fields = Fields::new(line)?;
+        let message = match fields.name {
+            "welcome" => ServerMessage::Welcome {
+                version: fields.number("version")?,
+                features: decode_features(fields.next("features")?),
+            },
+            "rejected" => ServerMessage::Rejected { reason: unescape(fields.next("reason")?) },
+            "lobby" => ServerMessage::Lobby { players: fields.number("players")? },
+            "tables-full" => ServerMessage::TablesFull,
+            "seated" => ServerMessage::Seated {
+                seat: fields.number("seat")?,
+                first: fields.number("first")?,
+                names: fields.rest().map(unescape).collect(),
+            },
+            "your-turn" => ServerMessage::YourTurn,
+            "pile" => ServerMessage::Pile {
+                size: fields.number("size")?,
+                top: fields.cards()?,
+            },
+            "hands" => {
+                let mut counts = Vec::new();
+                for count in fields.rest() {
+                    let mut parts = count.splitn(2, ',').map(|n| n.parse());
+                    match (parts.next(), parts.next()) {
+                        (Some(Ok(hand)), Some(Ok(side))) => counts.push((hand, side)),
+                        _ => return Err(ProtocolError::BadField("count", count.to_string())),
+                    }
+                }
+                ServerMessage::Hands { counts }
+            }
+            "cant-slap" => ServerMessage::CantSlap,
+            "event" => ServerMessage::Event(decode_event(&mut fields)?),
+            "notice" => ServerMessage::Notice(unescape(fields.next("text")?)),
+            name => return Err(fields.unknown(name)),
+        };
+        Ok(message)
+    }
+}
+
+/// Writes the fields of a game event
+fn encode_event(event: &Event, line: &mut Vec<String>) {
+    match *event {
+        Event::CardPlayed { player, card } => {
+            line.push("card-played".to_string());
+            line.push(player.to_string());
+            line.push(render::code(card));
+        }
+        Event::ChallengeStarted { challenger, challenged, card, count } => {
+            line.push("challenge".to_string());
+            line.push(challenger.to_string());
+            line.push(challenged.to_string());
+            line.push(render::code(card));
+            line.push(count.to_string());
+        }
+        Event::Slapped { player, ref found, ref cards } => {
+            line.push("slapped".to_string());
+            line.push(player.to_string());
+            line.push(found.pattern.name().to_string());
+            let indices: Vec<String> = found.indices.iter().map(|i| i.to_string()).collect();
+            line.push(indices.join(","));
+            line.extend(cards.iter().map(|&card| render::code(card)));
+        }
+        Event::FalseSlap { player, penalty } => {
+            line.push("false-slap".to_string());
+            line.push(player.to_string());
+            line.push(penalty.name());
+        }
+        Event::Burned { player, ref cards } => {
+            line.push("burned".to_string());
+            line.push(player.to_string());
+            line.extend(cards.iter().map(|&card| render::code(card)));
+        }
+        Event::PileTaken { player, cards, reason } => {
+            line.push("pile-taken".to_string());
+            line.push(player.to_string());
+            line.push(cards.to_string());
+            line.push(match reason {
+                TakeReason::Slap => "slap",
+                TakeReason::FalseSlap => "false-slap",
+                TakeReason::Challenge => "challenge",
+                TakeReason::LastCard => "last-card",
+            }.to_string());
+        }
+        Event::OutOfCards { player } => {
+            line.push("out-of-cards".to_string());
+            line.push(player.to_string());
+        }
+        Event::GameWon { player } => {
+            line.push("game-won".to_string());
+            line.push(player.to_string());
+        }
+        Event::PlayerQuit { player } => {
+            line.push("player-quit".to_string());
+            line.push(player.to_string());
+        }
+    }
+}
+
+/// Finds a slap pattern by name. Logs from before format 3 name the
+/// patterns the way version 2 of the protocol did, so those names are
+/// read as well.
+pub fn read_pattern(name: &str) -> Option<SlapPattern> {
+    SlapPattern::from_name(name).or_else(|| {
+        let pattern = match name {
+            "Pair" => SlapPattern::Pair,
+            "Sandwich" => SlapPattern::Sandwich,
+            "SixtyNine" => SlapPattern::SixtyNine,
+            "SixtyNineSandwich" => SlapPattern::SixtyNineSandwich,
+            "Run" => SlapPattern::Run,
+            "TopBottom" => SlapPattern::TopBottom,
+            "Marriage" => SlapPattern::Marriage,
+            "Divorce" => SlapPattern::Divorce,
+            "AddToTen" => SlapPattern::AddToTen,
+            "Ten" => SlapPattern::Ten,
+            "FourInARow" => SlapPattern::FourInARow,
+            "Joker" => SlapPattern::Joker,
+            _ => return None,
+        };
+        Some(pattern)
+    })
+}
+
+/// Reads the fields of a game event
+fn decode_event(fields: &mut Fields) -> Result<Event, ProtocolError> {
+    let event = match fields.next("event")? {
+        "card-played" => Event::CardPlayed {
+            player: fields.number("player")?,
+            card: fields.card("card")?,
+        },
+        "challenge" => Event::ChallengeStarted {
+            challenger: fields.number("challenger")?,
+            challenged: fields.number("challenged")?,
+            card: fields.card("card")?,
+            count: fields.number("count")?,
+        },
+        "slapped" => {
+            let player = fields.number("player")?;
+            let pattern = fields.next("pattern")?;
+            let pattern = read_pattern(pattern)
+                .ok_or_else(|| ProtocolError::BadField("pattern", pattern.to_string()))?;
+            let indices = fields.next("indices")?;
+            let indices = indices.split(',')
+                .map(|i| i.parse())
+                .collect::<Result<Vec<usize>, _>>()
+                .map_err(|_| ProtocolError::BadField("indices", indices.to_string()))?;
+            Event::Slapped {
+                player,
+                found: SlapMatch { pattern, indices },
+                cards: fields.cards()?,
+            }
+        }
+        "false-slap" => {
+            let player = fields.number("player")?;
+            let penalty = fields.next("penalty")?;
+            let penalty = Penalty::from_name(penalty)
+                .ok_or_else(|| ProtocolError::BadField("penalty", penalty.to_string()))?;
+            Event::FalseSlap { player, penalty }
+        }
+        "burned" => Event::Burned {
+            player: fields.number("player")?,
+            cards: fields.cards()?,
+        },
+        "pile-taken" => {
+            let player = fields.number("player")?;
+            let cards = fields.number("cards")?;
+            let reason = match fields.next("reason")? {
+                "slap" => TakeReason::Slap,
+                "false-slap" => TakeReason::FalseSlap,
+                "challenge" => TakeReason::Challenge,
+                "last-card" => TakeReason::LastCard,
+                reason => return Err(ProtocolError::BadField("reason", reason.to_string())),
+            };
+            Event::PileTaken { player, cards, reason }
+        }
+        "out-of-cards" => Event::OutOfCards { player: fields.number("player")? },
+        "game-won" => Event::GameWon { player: fields.number("player")? },
+        "player-quit" => Event::PlayerQuit { player: fields.number("player")? },
+        event => return Err(ProtocolError::BadField("event", event.to_string())),
+    };
+    Ok(event)
+}
+
+/// The fields of a line, after the message name
+struct Fields<'a> {
+    name: &'a str,
+    fields: Split<'a, char>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(line: &'a str) -> Result<Fields<'a>, ProtocolError> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Err(ProtocolError::Empty);
+        }
+        let mut fields = line.split(' ');
+        let name = fields.next().unwrap_or("");
+        Ok(Fields { name, fields })
+    }
+
+    fn unknown(&self, name: &str) -> ProtocolError {
+        ProtocolError::UnknownMessage(name.to_string())
+    }
+
+    fn next(&mut self, field: &'static str) -> Result<&'a str, ProtocolError> {
+        self.fields.next().ok_or(ProtocolError::MissingField(field))
+    }
+
+    fn number<T: ::std::str::FromStr>(&mut self, field: &'static str) -> Result<T, ProtocolError> {
+        let value = self.next(field)?;
+        value.parse().map_err(|_| ProtocolError::BadField(field, value.to_string()))
+    }
+
+    fn card(&mut self, field: &'static str) -> Result<Card, ProtocolError> {
+        let value = self.next(field)?;
+        parse_card_code(value).ok_or_else(|| ProtocolError::BadField(field, value.to_string()))
+    }
+
+    // Every field left is a card
+    fn cards(&mut self) -> Result<Vec<Card>, ProtocolError> {
+        self.rest()
+            .map(|value| parse_card_code(value)
+                 .ok_or_else(|| ProtocolError::BadField("card", value.to_string())))
+            .collect()
+    }
+
+    fn rest(&mut self) -> &mut Split<'a, char> {
+        &mut self.fields
+    }
+}
+
+/// Writes a list of features separated by commas, or - for none
+fn encode_features(features: &[Feature]) -> String {
+    if features.is_empty() {
+        return "-".to_string();
+    }
+    let names: Vec<&str> = features.iter().map(|f| f.name()).collect();
+    names.join(",")
+}
+
+/// Reads a list of features, skipping any this version doesn't know
+fn decode_features(field: &str) -> Vec<Feature> {
+    field.split(',')
+         .filter_map(|name| Feature::all().iter().find(|f| f.name() == name).cloned())
+         .collect()
+}
+
+/// Reads a card from its short code. Only the codes `render::code` writes
+/// are taken, so every card has one way to be sent.
+fn parse_card_code(code: &str) -> Option<Card> {
+    code.parse().ok().filter(|&card| render::code(card) == code)
+}
+
+/// Escapes a text field so it holds no spaces
+pub fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(' ', "\\s")
+}
+
+/// Reverses escape
+pub fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Rank;
+    use Suit::*;
+
+    #[test]
+    fn test_client_messages_round_trip() {
+        let hello = ClientMessage::Hello { version: VERSION, features: vec![Feature::Unicode],
+                                           name: "Cole P".to_string() };
+        for message in [hello, ClientMessage::Deal, ClientMessage::PlayCard,
+                            ClientMessage::Slap, ClientMessage::Quit] {
+            assert_eq!(ClientMessage::decode(&message.encode()), Ok(message));
+        }
+        assert_eq!(ClientMessage::decode("slap\r\n"), Ok(ClientMessage::Slap));
+        assert_eq!(ClientMessage::decode("hello 2 - \r\n"),
+                   Ok(ClientMessage::Hello { version: 2, features: vec![], name: String::new() }));
+        assert_eq!(ClientMessage::decode(""), Err(ProtocolError::Empty));
+        assert_eq!(ClientMessage::decode("dance"),
+                   Err(ProtocolError::UnknownMessage("dance".to_string())));
+    }
+
+    #[test]
+    fn test_server_messages_round_trip() {
+        let messages = vec![
+            ServerMessage::Welcome { version: VERSION, features: vec![] },
+            ServerMessage::Rejected { reason: "Too old".to_string() },
+            ServerMessage::Seated { seat: 1, first: 0,
+                                    names: vec!["Player 1".to_string(), "Back\\slash".to_string()] },
+            ServerMessage::Pile { size: 12, top: vec![Card::new(Rank::Num(10), Hearts),
+                                                      Card::new(Rank::Joker, Spades)] },
+            ServerMessage::Hands { counts: vec![(20, 3), (0, 0), (7, 12)] },
+            ServerMessage::Notice("The server is shutting down.".to_string()),
+            ServerMessage::Event(Event::Slapped {
+                player: 2,
+                found: SlapMatch { pattern: SlapPattern::SixtyNineSandwich, indices: vec![3, 4, 5] },
+                cards: vec![Card::new(Rank::Num(6), Clubs), Card::new(Rank::Ace, Clubs),
+                            Card::new(Rank::Num(9), Diamonds)],
+            }),
+            ServerMessage::Event(Event::FalseSlap { player: 0, penalty: Penalty::Burn(2) }),
+            ServerMessage::Event(Event::PileTaken { player: 1, cards: 7, reason: TakeReason::LastCard }),
+        ];
+        for message in messages {
+            let line = message.encode();
+            assert!(!line.contains('\n'));
+            assert_eq!(ServerMessage::decode(&line), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let supported = [Feature::Colour, Feature::Unicode];
+
+        assert_eq!(negotiate(VERSION + 1, &[Feature::Unicode, Feature::Spectator], &supported),
+                   Ok(ServerMessage::Welcome { version: VERSION, features: vec![Feature::Unicode] }));
+        assert_eq!(negotiate(OLDEST_VERSION - 1, &[], &supported),
+                   Err(HandshakeError::TooOld(OLDEST_VERSION - 1)));
+        assert_eq!(decode_features("colour,telepathy,spectator"),
+                   vec![Feature::Colour, Feature::Spectator]);
+        assert_eq!(decode_features("-"), vec![]);
+    }
+
+    #[test]
+    fn test_card_codes() {
+        let card = Card::new(Rank::Queen, Diamonds);
+
+        assert_eq!(render::code(card), "QD");
+        assert_eq!(parse_card_code("10S").map(|c| c.rank), Some(Rank::Num(10)));
+        assert_eq!(parse_card_code("QD").map(|c| c.suit), Some(Diamonds));
+        assert_eq!(parse_card_code("1H"), None);
+        assert_eq!(parse_card_code("7"), None);
+    }
+
+    #[test]
+    fn test_bad_fields() {
+        assert_eq!(ServerMessage::decode("lobby"), Err(ProtocolError::MissingField("players")));
+        assert_eq!(ServerMessage::decode("event card-played 0 ZZ"),
+                   Err(ProtocolError::BadField("card", "ZZ".to_string())));
+        assert_eq!(ServerMessage::decode("lobby 3 extra"), Ok(ServerMessage::Lobby { players: 3 }));
+    }
+}
@@ -0,0 +1,305 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Ways to write cards out for players to read
+
+//! Writing cards out for players to read.
+//!
+//! A `Style` turns a card into text on one line, from "7 of Hearts" down to
+//! the two character code "7H", and draws cards as boxes several lines tall.
+//! Not every terminal can show every style: `Style::detect` picks the best
+//! one the terminal the program runs in can show.
+
+use std::env;
+use {Card, Rank, Suit};
+
+/// Width and height of a card drawn as a box
+pub const ART_WIDTH: usize = 7;
+pub const ART_HEIGHT: usize = 5;
+
+/// Switches red text on and back off again
+const RED: &str = "\x1b[31m";
+const DEFAULT_COLOUR: &str = "\x1b[39m";
+
+/// How cards are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Style {
+    /// "7 of Hearts", the same as Display
+    #[default]
+    Words,
+    /// "7H", the same as the protocol
+    Codes,
+    /// "7♥"
+    Symbols,
+    /// "7♥" with hearts and diamonds in red
+    Colour,
+    /// One Unicode playing card, "🂷"
+    Unicode,
+}
+
+impl Style {
+    /// Every style, plainest first
+    pub fn all() -> [Style; 5] {
+        [Style::Words, Style::Codes, Style::Symbols, Style::Colour, Style::Unicode]
+    }
+
+    /// Lower case name of the style
+    pub fn name(self) -> &'static str {
+        match self {
+            Style::Words => "words",
+            Style::Codes => "codes",
+            Style::Symbols => "symbols",
+            Style::Colour => "colour",
+            Style::Unicode => "unicode",
+        }
+    }
+
+    /// Finds a style by name
+    pub fn from_name(name: &str) -> Option<Style> {
+        Style::all().iter().find(|s| s.name() == name).cloned()
+    }
+
+    /// Picks a style for a terminal that can or can't show colour and
+    /// characters outside of ASCII
+    pub fn pick(colour: bool, unicode: bool) -> Style {
+        match (unicode, colour) {
+            (true, true) => Style::Colour,
+            (true, false) => Style::Symbols,
+            (false, _) => Style::Words,
+        }
+    }
+
+    /// Picks a style for the terminal the program runs in, going by its
+    /// environment
+    pub fn detect() -> Style {
+        let term = env::var("TERM").unwrap_or_default();
+        let colour = !term.is_empty() && term != "dumb" && env::var_os("NO_COLOR").is_none();
+        // The first locale variable set is the one in use
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+                         .filter_map(|name| env::var(name).ok())
+                         .find(|value| !value.is_empty())
+                         .unwrap_or_default();
+        Style::pick(colour, is_utf8(&locale))
+    }
+
+    /// Whether the style uses characters outside of ASCII
+    pub fn unicode(self) -> bool {
+        match self {
+            Style::Words | Style::Codes => false,
+            Style::Symbols | Style::Colour | Style::Unicode => true,
+        }
+    }
+
+    /// Whether the style colours the red suits
+    pub fn colour(self) -> bool {
+        self == Style::Colour
+    }
+
+    /// Writes a card out on one line
+    pub fn card(self, card: Card) -> String {
+        match self {
+            Style::Words => card.to_string(),
+            Style::Codes => code(card),
+            Style::Symbols => format!("{}{}", rank_code(card.rank), symbol(card.suit)),
+            Style::Colour => {
+                let text = format!("{}{}", rank_code(card.rank), symbol(card.suit));
+                paint(&text, card.suit.is_red())
+            }
+            Style::Unicode => unicode(card).to_string(),
+        }
+    }
+
+    /// Draws a card as a box, rank in the corners and suit in the middle
+    pub fn art(self, card: Card) -> Vec<String> {
+        self.spread(&[card], ART_WIDTH)
+    }
+
+    /// Draws cards as boxes laid over each other from left to right, `step`
+    /// columns apart, so the last card shows in full
+    pub fn spread(self, cards: &[Card], step: usize) -> Vec<String> {
+        if cards.is_empty() {
+            return Vec::new();
+        }
+        let width = ART_WIDTH + step * (cards.len() - 1);
+        // Every cell remembers whether it's red
+        let mut grid = vec![vec![(' ', false); width]; ART_HEIGHT];
+        for (i, &card) in cards.iter().enumerate() {
+            let red = self.colour() && card.suit.is_red();
+            for (row, line) in self.lines(card).iter().enumerate() {
+                for (column, c) in line.chars().enumerate() {
+                    // Only the rank and the suit take the colour
+                    let edge = row == 0 || row == ART_HEIGHT - 1 || column == 0 || column == ART_WIDTH - 1;
+                    grid[row][i * step + column] = (c, red && !edge);
+                }
+            }
+        }
+        grid.into_iter().map(|row| {
+            let mut line = String::new();
+            let mut red = false;
+            for (c, cell_red) in row {
+                if cell_red != red {
+                    line.push_str(if cell_red { RED } else { DEFAULT_COLOUR });
+                    red = cell_red;
+                }
+                line.push(c);
+            }
+            if red {
+                line.push_str(DEFAULT_COLOUR);
+            }
+            line
+        }).collect()
+    }
+
+    // The lines of a card box, without colour
+    fn lines(self, card: Card) -> [String; ART_HEIGHT] {
+        let rank = rank_code(card.rank);
+        let rank = if card.rank == Rank::Joker { "Jk" } else { rank.as_str() };
+        if self.unicode() {
+            let suit = symbol(card.suit);
+            [
+                "┌─────┐".to_string(),
+                format!("│{:<5}│", rank),
+                format!("│  {}  │", suit),
+                format!("│{:>5}│", rank),
+                "└─────┘".to_string(),
+            ]
+        } else {
+            let suit = suit_code(card.suit);
+            [
+                "+-----+".to_string(),
+                format!("|{:<5}|", rank),
+                format!("|  {}  |", suit),
+                format!("|{:>5}|", rank),
+                "+-----+".to_string(),
+            ]
+        }
+    }
+}
+
+/// Short code for a card: the rank then the suit, "7H", "10S", "QD". Jokers
+/// are "X" followed by their suit.
+pub fn code(card: Card) -> String {
+    format!("{}{}", rank_code(card.rank), suit_code(card.suit))
+}
+
+/// Symbol for a suit, "♥"
+pub fn symbol(suit: Suit) -> char {
+    match suit {
+        Suit::Hearts => '♥',
+        Suit::Diamonds => '♦',
+        Suit::Spades => '♠',
+        Suit::Clubs => '♣',
+    }
+}
+
+/// The Unicode playing card for a card. Jokers of the red suits are the red
+/// joker, the others the black joker.
+pub fn unicode(card: Card) -> char {
+    let rank = match card.rank {
+        Rank::Ace => 0x1,
+        Rank::Num(n) => n,
+        Rank::Jack => 0xB,
+        // 0xC is the knight, which isn't in our decks
+        Rank::Queen => 0xD,
+        Rank::King => 0xE,
+        Rank::Joker => return if card.suit.is_red() { '\u{1F0BF}' } else { '\u{1F0CF}' },
+    };
+    let suit = match card.suit {
+        Suit::Spades => 0x1F0A0,
+        Suit::Hearts => 0x1F0B0,
+        Suit::Diamonds => 0x1F0C0,
+        Suit::Clubs => 0x1F0D0,
+    };
+    ::std::char::from_u32(suit + rank).unwrap_or('?')
+}
+
+/// Whether a locale such as "en_US.UTF-8" uses UTF-8
+fn is_utf8(locale: &str) -> bool {
+    let locale = locale.to_lowercase();
+    locale.ends_with("utf-8") || locale.ends_with("utf8")
+}
+
+fn paint(text: &str, red: bool) -> String {
+    if red {
+        format!("{}{}{}", RED, text, DEFAULT_COLOUR)
+    } else {
+        text.to_string()
+    }
+}
+
+fn rank_code(rank: Rank) -> String {
+    match rank {
+        Rank::Num(n) => n.to_string(),
+        Rank::Jack => "J".to_string(),
+        Rank::Queen => "Q".to_string(),
+        Rank::King => "K".to_string(),
+        Rank::Ace => "A".to_string(),
+        Rank::Joker => "X".to_string(),
+    }
+}
+
+fn suit_code(suit: Suit) -> char {
+    match suit {
+        Suit::Hearts => 'H',
+        Suit::Diamonds => 'D',
+        Suit::Spades => 'S',
+        Suit::Clubs => 'C',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Card;
+    use {Rank::*, Suit::*};
+
+    #[test]
+    fn test_one_line() {
+        let seven = Card { rank: Num(7), suit: Hearts };
+        let queen = Card { rank: Queen, suit: Spades };
+        assert_eq!(Style::Words.card(seven), "7 of Hearts");
+        assert_eq!(Style::Codes.card(seven), "7H");
+        assert_eq!(Style::Codes.card(Card { rank: Num(10), suit: Clubs }), "10C");
+        assert_eq!(Style::Symbols.card(queen), "Q♠");
+        assert_eq!(Style::Colour.card(seven), "\x1b[31m7♥\x1b[39m");
+        assert_eq!(Style::Colour.card(queen), "Q♠");
+        assert_eq!(Style::Unicode.card(seven), "🂷");
+    }
+
+    #[test]
+    fn test_unicode() {
+        assert_eq!(unicode(Card { rank: Ace, suit: Spades }), '\u{1F0A1}');
+        assert_eq!(unicode(Card { rank: Queen, suit: Hearts }), '\u{1F0BD}');
+        assert_eq!(unicode(Card { rank: King, suit: Clubs }), '\u{1F0DE}');
+        assert_eq!(unicode(Card { rank: Num(10), suit: Diamonds }), '\u{1F0CA}');
+        assert_eq!(unicode(Card { rank: Joker, suit: Diamonds }), '\u{1F0BF}');
+        assert_eq!(unicode(Card { rank: Joker, suit: Spades }), '\u{1F0CF}');
+    }
+
+    #[test]
+    fn test_art() {
+        let ten = Card { rank: Num(10), suit: Spades };
+        assert_eq!(Style::Words.art(ten), vec!["+-----+", "|10   |", "|  S  |", "|   10|", "+-----+"]);
+        assert_eq!(Style::Symbols.art(ten)[2], "│  ♠  │");
+
+        // Only the last card shows in full
+        let cards = [Card { rank: Num(2), suit: Hearts }, ten];
+        let spread = Style::Codes.spread(&cards, 4);
+        assert_eq!(spread[1], "|2  |10   |");
+        assert!(spread.iter().all(|line| line.chars().count() == ART_WIDTH + 4));
+
+        let red = Style::Colour.art(Card { rank: Ace, suit: Diamonds });
+        assert_eq!(red[2], "│\x1b[31m  ♦  \x1b[39m│");
+    }
+
+    #[test]
+    fn test_pick() {
+        assert_eq!(Style::pick(true, true), Style::Colour);
+        assert_eq!(Style::pick(false, true), Style::Symbols);
+        assert_eq!(Style::pick(true, false), Style::Words);
+        assert!(is_utf8("en_US.UTF-8") && is_utf8("C.utf8") && !is_utf8("C"));
+        for &style in Style::all().iter() {
+            assert_eq!(Style::from_name(style.name()), Some(style));
+        }
+    }
+}
@@ -0,0 +1,592 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Game logs for Egyptian RatScrew
+
+//! Logs of finished games, and replaying them through the engine.
+//!
+//! A log is a text file with one entry per line. It opens with a header
+//! that is enough to deal the same game again:
+//!
+//! ```text
+//! ratscrew-log 3
+//! seed 8214566390711470781
+//! deck 1 2 0
+//! rules pair,sandwich,sixty_nine,sixty_nine_sandwich,run
+//! players Alice Computer
+//! penalty burn:1
+//! ```
+//!
+//! `deck` gives the number of decks, the lowest number card and the jokers
+//! per deck. `rules` names the combinations that can be slapped by the
+//! names of their settings, or `-` for none. Logs before format 3 name them
+//! the way version 2 of the wire protocol did. Player names are escaped the
+//! same way as in the wire protocol. `penalty` is what a false slap costs,
+//! written the same way as in the `false-slap` event. Logs in format 1 have
+//! no `penalty` line, their games were played with the default penalty.
+//!
+//! Then comes every action the table played, as the milliseconds since the
+//! cards were dealt, the action (`play`, `slap`, `collect` or `quit`) and
+//! the seat that made it. A slap is timed from when the player slapped, not
+//! when the server got round to it. Each action is followed by the events
+//! it caused, written as the `event` lines of the wire protocol. Slaps that
+//! lost the race to another slap are written as `late`, they don't change
+//! the game. The log ends with `end` and the winning seat, or `-` if the
+//! game stopped without a winner.
+//!
+//! ```text
+//! 0 play 1
+//! event card-played 1 8H
+//! 1530 play 0
+//! event card-played 0 8C
+//! 1912 slap 1
+//! event slapped 1 pair 0,1 8H 8C
+//! event pile-taken 1 2 slap
+//! 1958 late 0
+//! ```
+
+use std::collections::VecDeque;
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::time::Instant;
+use seeded;
+use deck::DeckSpec;
+use engine::{Action, ActionError, Event, GameState, Penalty};
+use protocol::{escape, read_pattern, unescape, ServerMessage};
+use rules::{RuleSet, SlapPattern};
+
+/// Version of the log format described by this module
+pub const FORMAT: u32 = 3;
+
+/// First word of every log
+const MAGIC: &str = "ratscrew-log";
+
+/// Lines in the header of a log in format 1, later formats add a
+/// `penalty` line
+const HEADER_LINES: usize = 5;
+
+/// What it takes to deal a game again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    /// Seed the game was dealt from
+    pub seed: u64,
+    pub deck: DeckSpec,
+    pub rules: RuleSet,
+    /// Names of the players, in seat order
+    pub names: Vec<String>,
+    pub penalty: Penalty,
+}
+
+impl Header {
+    /// Deals the game the header describes
+    pub fn deal(&self) -> GameState {
+        GameState::shuffled(self.deck.build(), self.names.len(), &mut seeded(self.seed))
+            .with_rules(self.rules)
+            .with_penalty(self.penalty)
+    }
+}
+
+/// A line of the log after the header
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    /// An action the table played, with the milliseconds since the deal
+    Action { at: u64, action: Action },
+    /// A slap that lost the race to an earlier one
+    Late { at: u64, player: usize },
+    /// Something the last action caused
+    Event(Event),
+    /// The game stopped, with the winner if it had one
+    End(Option<usize>),
+}
+
+/// A game read back from its log
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameLog {
+    /// Format the log was written in
+    pub format: u32,
+    pub header: Header,
+    pub entries: Vec<Entry>,
+}
+
+/// Reasons a log can't be read or doesn't replay
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogError {
+    /// The file couldn't be read
+    Io(String),
+    /// The file isn't a game log
+    NotALog,
+    /// The log was written in a newer format
+    Format(u32),
+    /// A line couldn't be read, with its number and what's wrong
+    BadLine(usize, String),
+    /// The engine refused an action in the log
+    Refused(usize, ActionError),
+    /// The log and the engine disagree about what happened
+    Differs(usize, String, String),
+}
+
+impl fmt::Display for LogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LogError::Io(ref reason) => write!(f, "Can't read the log: {}", reason),
+            LogError::NotALog => write!(f, "Not a game log"),
+            LogError::Format(format) => write!(f, "The log is in format {}, newer than {}", format, FORMAT),
+            LogError::BadLine(line, ref reason) => write!(f, "Line {}: {}", line, reason),
+            LogError::Refused(line, ref err) => write!(f, "Line {}: {}", line, err),
+            LogError::Differs(line, ref logged, ref replayed) => {
+                write!(f, "Line {}: the log says {} but the game says {}", line, logged, replayed)
+            }
+        }
+    }
+}
+
+impl error::Error for LogError {}
+
+impl GameLog {
+    /// Reads a log
+    pub fn read<R: BufRead>(reader: R) -> Result<GameLog, LogError> {
+        let mut lines = Vec::new();
+        for line in reader.lines() {
+            lines.push(line.map_err(|err| LogError::Io(err.to_string()))?);
+        }
+        let format = match lines.first().and_then(|line| header_field(line, MAGIC)) {
+            Some(format) => format.parse().map_err(|_| LogError::NotALog)?,
+            None => return Err(LogError::NotALog),
+        };
+        if format > FORMAT {
+            return Err(LogError::Format(format));
+        }
+        let header_lines = header_lines(format);
+        if lines.len() < header_lines {
+            return Err(LogError::NotALog);
+        }
+        let header = Header {
+            seed: number(2, header_field(&lines[1], "seed"))?,
+            deck: read_deck(header_field(&lines[2], "deck"))?,
+            rules: read_rules(header_field(&lines[3], "rules"))?,
+            names: header_field(&lines[4], "players")
+                .ok_or_else(|| bad(5, "missing players"))?
+                .split(' ')
+                .map(unescape)
+                .collect(),
+            penalty: match format {
+                1 => Penalty::default(),
+                _ => {
+                    let field = header_field(&lines[5], "penalty").ok_or_else(|| bad(6, "missing penalty"))?;
+                    Penalty::from_name(field).ok_or_else(|| bad(6, &format!("bad penalty {}", field)))?
+                }
+            },
+        };
+        if header.names.len() < 2 {
+            return Err(bad(5, "a game needs at least two players"));
+        }
+
+        let mut entries = Vec::new();
+        for (i, line) in lines.iter().enumerate().skip(header_lines) {
+            let entry = read_entry(i + 1, line)?;
+            match seat(&entry) {
+                Some(player) if player >= header.names.len() => {
+                    return Err(bad(i + 1, &format!("no player in seat {}", player)));
+                }
+                _ => entries.push(entry),
+            }
+        }
+        Ok(GameLog { format, header, entries })
+    }
+
+    // Number of the line an entry was read from, counting from 1
+    fn line(&self, entry: usize) -> usize {
+        header_lines(self.format) + entry + 1
+    }
+
+    /// Plays the log through the engine, checking every event the engine
+    /// comes up with against the log. Returns the game as it stood at the
+    /// end of the log.
+    pub fn replay(&self) -> Result<GameState, LogError> {
+        let mut game = self.header.deal();
+        let mut pending: VecDeque<Event> = VecDeque::new();
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let line = self.line(i);
+            match *entry {
+                Entry::Action { action, .. } => {
+                    if let Some(event) = pending.pop_front() {
+                        return Err(LogError::Differs(line, describe(entry), event_line(&event)));
+                    }
+                    pending = game.apply(action)
+                                  .map_err(|err| LogError::Refused(line, err))?
+                                  .into();
+                }
+                Entry::Event(ref logged) => {
+                    match pending.pop_front() {
+                        Some(ref event) if event == logged => {},
+                        Some(event) => return Err(LogError::Differs(line, describe(entry), event_line(&event))),
+                        None => return Err(LogError::Differs(line, describe(entry), "nothing".to_string())),
+                    }
+                }
+                Entry::Late { .. } => {},
+                Entry::End(winner) => {
+                    if winner != game.winner() {
+                        return Err(LogError::Differs(line, describe(entry), describe(&Entry::End(game.winner()))));
+                    }
+                }
+            }
+        }
+        if let Some(event) = pending.pop_front() {
+            return Err(LogError::Differs(self.line(self.entries.len()),
+                                         "nothing".to_string(), event_line(&event)));
+        }
+        Ok(game)
+    }
+
+    /// Replays the log and keeps the game as it stood after every action,
+    /// for stepping back and forth through it
+    pub fn steps(&self) -> Result<Vec<Step>, LogError> {
+        self.replay()?;
+        let mut game = self.header.deal();
+        let mut steps: Vec<Step> = Vec::new();
+        for (i, entry) in self.entries.iter().enumerate() {
+            match *entry {
+                Entry::Action { at, action } => {
+                    let events = game.apply(action)
+                                     .map_err(|err| LogError::Refused(self.line(i), err))?;
+                    steps.push(Step {
+                        at,
+                        action,
+                        events,
+                        late: Vec::new(),
+                        game: game.clone(),
+                    });
+                }
+                Entry::Late { at, player } => {
+                    if let Some(step) = steps.last_mut() {
+                        step.late.push((at, player));
+                    }
+                }
+                Entry::Event(_) | Entry::End(_) => {},
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// An action in a log and what it led to
+#[derive(Debug, Clone)]
+pub struct Step {
+    /// Milliseconds since the deal
+    pub at: u64,
+    pub action: Action,
+    pub events: Vec<Event>,
+    /// Slaps that lost the race to this one, with when they were made
+    pub late: Vec<(u64, usize)>,
+    /// The game once the action was played
+    pub game: GameState,
+}
+
+/// Writes the log of a game as it is played
+pub struct Recorder<W: Write> {
+    writer: W,
+    // When the cards were dealt
+    start: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a log with its header, timing the game from now
+    pub fn new(mut writer: W, header: &Header) -> io::Result<Self> {
+        let names: Vec<String> = header.names.iter().map(|name| escape(name)).collect();
+        writeln!(writer, "{} {}", MAGIC, FORMAT)?;
+        writeln!(writer, "seed {}", header.seed)?;
+        writeln!(writer, "deck {} {} {}", header.deck.decks, header.deck.lowest, header.deck.jokers)?;
+        writeln!(writer, "rules {}", write_rules(&header.rules))?;
+        writeln!(writer, "players {}", names.join(" "))?;
+        writeln!(writer, "penalty {}", header.penalty.name())?;
+        writer.flush()?;
+        Ok(Recorder { writer, start: Instant::now() })
+    }
+
+    /// Logs an action made at `at` and the events it caused
+    pub fn action(&mut self, at: Instant, action: Action, events: &[Event]) -> io::Result<()> {
+        let at = self.millis(at);
+        writeln!(self.writer, "{}", describe(&Entry::Action { at, action }))?;
+        for event in events.iter() {
+            writeln!(self.writer, "{}", event_line(event))?;
+        }
+        self.writer.flush()
+    }
+
+    /// Logs a slap made at `at` that lost the race
+    pub fn late(&mut self, at: Instant, player: usize) -> io::Result<()> {
+        let at = self.millis(at);
+        writeln!(self.writer, "{}", describe(&Entry::Late { at, player }))?;
+        self.writer.flush()
+    }
+
+    /// Ends the log
+    pub fn end(&mut self, winner: Option<usize>) -> io::Result<()> {
+        writeln!(self.writer, "{}", describe(&Entry::End(winner)))?;
+        self.writer.flush()
+    }
+
+    // Milliseconds from the deal to `at`
+    fn millis(&self, at: Instant) -> u64 {
+        let since = at.duration_since(self.start.min(at));
+        since.as_secs() * 1000 + since.subsec_nanos() as u64 / 1_000_000
+    }
+}
+
+/// Writes an entry as its line in the log
+fn describe(entry: &Entry) -> String {
+    match *entry {
+        Entry::Action { at, action } => {
+            let (name, player) = match action {
+                Action::PlayCard(p) => ("play", p),
+                Action::Slap(p) => ("slap", p),
+                Action::Collect(p) => ("collect", p),
+                Action::Quit(p) => ("quit", p),
+            };
+            format!("{} {} {}", at, name, player)
+        }
+        Entry::Late { at, player } => format!("{} late {}", at, player),
+        Entry::Event(ref event) => event_line(event),
+        Entry::End(Some(winner)) => format!("end {}", winner),
+        Entry::End(None) => "end -".to_string(),
+    }
+}
+
+fn event_line(event: &Event) -> String {
+    ServerMessage::Event(event.clone()).encode()
+}
+
+/// Reads an entry from its line, numbered from 1
+fn read_entry(line: usize, text: &str) -> Result<Entry, LogError> {
+    if text.starts_with("event ") {
+        return match ServerMessage::decode(text) {
+            Ok(ServerMessage::Event(event)) => Ok(Entry::Event(event)),
+            Ok(_) => Err(bad(line, "not an event")),
+            Err(err) => Err(bad(line, &err.to_string())),
+        };
+    }
+
+    let fields: Vec<&str> = text.split(' ').collect();
+    match fields[..] {
+        ["end", "-"] => Ok(Entry::End(None)),
+        ["end", winner] => Ok(Entry::End(Some(number(line, Some(winner))?))),
+        [at, name, player] => {
+            let at = number(line, Some(at))?;
+            let player = number(line, Some(player))?;
+            let action = match name {
+                "play" => Action::PlayCard(player),
+                "slap" => Action::Slap(player),
+                "collect" => Action::Collect(player),
+                "quit" => Action::Quit(player),
+                "late" => return Ok(Entry::Late { at, player }),
+                _ => return Err(bad(line, &format!("unknown action {}", name))),
+            };
+            Ok(Entry::Action { at, action })
+        }
+        _ => Err(bad(line, "not an entry")),
+    }
+}
+
+/// Lines in the header of a log in a format
+fn header_lines(format: u32) -> usize {
+    if format < 2 { HEADER_LINES } else { HEADER_LINES + 1 }
+}
+
+/// Seat of the player an action, late slap or win is for
+fn seat(entry: &Entry) -> Option<usize> {
+    match *entry {
+        Entry::Action { action: Action::PlayCard(p), .. } |
+        Entry::Action { action: Action::Slap(p), .. } |
+        Entry::Action { action: Action::Collect(p), .. } |
+        Entry::Action { action: Action::Quit(p), .. } => Some(p),
+        Entry::Late { player, .. } => Some(player),
+        Entry::End(winner) => winner,
+        Entry::Event(_) => None,
+    }
+}
+
+/// The rest of a header line after its name
+fn header_field<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let mut parts = line.splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(found), Some(rest)) if found == name => Some(rest),
+        _ => None,
+    }
+}
+
+fn read_deck(field: Option<&str>) -> Result<DeckSpec, LogError> {
+    let field = field.ok_or_else(|| bad(3, "missing deck"))?;
+    let numbers = field.split(' ')
+                       .map(|n| number(3, Some(n)))
+                       .collect::<Result<Vec<u32>, _>>()?;
+    match numbers[..] {
+        [decks, lowest, jokers] => {
+            let deck = DeckSpec { decks, lowest, jokers };
+            if deck.is_valid() { Ok(deck) } else { Err(bad(3, &format!("bad deck {}", field))) }
+        }
+        _ => Err(bad(3, &format!("bad deck {}", field))),
+    }
+}
+
+/// Names the rules that are on, or - for none
+fn write_rules(rules: &RuleSet) -> String {
+    let names: Vec<&str> = SlapPattern::all().iter()
+        .filter(|&&pattern| rules.allows(pattern))
+        .map(|pattern| pattern.name())
+        .collect();
+    if names.is_empty() {
+        return "-".to_string();
+    }
+    names.join(",")
+}
+
+fn read_rules(field: Option<&str>) -> Result<RuleSet, LogError> {
+    let field = field.ok_or_else(|| bad(4, "missing rules"))?;
+    let mut rules = RuleSet::classic();
+    for &pattern in SlapPattern::all().iter() {
+        rules.set(pattern, false);
+    }
+    for name in field.split(',').filter(|&name| name != "-") {
+        let pattern = read_pattern(name)
+            .ok_or_else(|| bad(4, &format!("unknown rule {}", name)))?;
+        rules.set(pattern, true);
+    }
+    Ok(rules)
+}
+
+fn number<T: ::std::str::FromStr>(line: usize, field: Option<&str>) -> Result<T, LogError> {
+    let field = field.ok_or_else(|| bad(line, "missing field"))?;
+    field.parse().map_err(|_| bad(line, &format!("bad number {}", field)))
+}
+
+fn bad(line: usize, reason: &str) -> LogError {
+    LogError::BadLine(line, reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn header() -> Header {
+        Header {
+            seed: 12,
+            deck: DeckSpec::standard().stripped(9),
+            rules: RuleSet::all(),
+            names: vec!["Alice Smith".to_string(), "Computer".to_string()],
+            penalty: Penalty::Burn(2),
+        }
+    }
+
+    // Records a game where the players take turns and player 0 slaps
+    // whatever can be slapped
+    fn record(header: &Header) -> Vec<u8> {
+        let mut recorder = Recorder::new(Vec::new(), header).unwrap();
+        let mut game = header.deal();
+        let start = Instant::now();
+        let mut turns = 0;
+        while !game.is_over() && turns < 5000 {
+            let at = start + Duration::from_millis(turns * 100);
+            let action = if game.slappable() && game.can_slap(0) {
+                Action::Slap(0)
+            } else if let Some(claim) = game.claim() {
+                Action::Collect(claim.player)
+            } else {
+                Action::PlayCard(game.turn())
+            };
+            let events = game.apply(action).unwrap();
+            recorder.action(at, action, &events).unwrap();
+            if let Action::Slap(_) = action {
+                recorder.late(at + Duration::from_millis(40), 1).unwrap();
+            }
+            turns += 1;
+        }
+        recorder.end(game.winner()).unwrap();
+        recorder.writer
+    }
+
+    #[test]
+    fn test_log_replays() {
+        let header = header();
+        let text = record(&header);
+        let log = GameLog::read(&text[..]).unwrap();
+
+        assert_eq!(log.header, header);
+        assert!(log.entries.iter().any(|entry| matches!(*entry, Entry::Late { player: 1, .. })));
+        let game = log.replay().unwrap();
+        assert_eq!(Some(&Entry::End(game.winner())), log.entries.last());
+
+        let steps = log.steps().unwrap();
+        assert_eq!(steps.last().unwrap().game.winner(), game.winner());
+        assert!(steps.iter().any(|step| !step.late.is_empty()));
+        assert!(steps.windows(2).all(|pair| pair[0].at <= pair[1].at));
+    }
+
+    #[test]
+    fn test_tampered_log() {
+        let text = String::from_utf8(record(&header())).unwrap();
+
+        // Someone claims player 1 slapped first
+        let tampered = text.replacen(" slap 0", " slap 1", 1);
+        match GameLog::read(tampered.as_bytes()).unwrap().replay() {
+            Err(LogError::Differs(..)) | Err(LogError::Refused(..)) => {},
+            other => panic!("Tampered log replayed: {:?}", other),
+        }
+
+        // Or leaves out the first card played
+        let mut lines: Vec<&str> = text.lines().collect();
+        let header_lines = header_lines(FORMAT);
+        lines.remove(header_lines + 1);
+        match GameLog::read(lines.join("\n").as_bytes()).unwrap().replay() {
+            Err(LogError::Differs(line, _, replayed)) => {
+                assert_eq!(line, header_lines + 2);
+                assert!(replayed.starts_with("event card-played"));
+            }
+            other => panic!("Cut log replayed: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bad_logs() {
+        assert_eq!(GameLog::read(&b"hello 2 - Alice\n"[..]), Err(LogError::NotALog));
+        assert_eq!(GameLog::read(&b"ratscrew-log 9\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\n"[..]),
+                   Err(LogError::Format(9)));
+        assert_eq!(GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 4000000000 2 0\nrules -\nplayers a b\n"[..]),
+                   Err(LogError::BadLine(3, "bad deck 4000000000 2 0".to_string())));
+        assert_eq!(GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 1 2 0\nrules Pear\nplayers a b\n"[..]),
+                   Err(LogError::BadLine(4, "unknown rule Pear".to_string())));
+        assert_eq!(GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\n0 jump 1\n"[..]),
+                   Err(LogError::BadLine(6, "unknown action jump".to_string())));
+        assert_eq!(GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\n0 slap 9\n"[..]),
+                   Err(LogError::BadLine(6, "no player in seat 9".to_string())));
+        assert_eq!(GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\n5 late 2\n"[..]),
+                   Err(LogError::BadLine(6, "no player in seat 2".to_string())));
+    }
+
+    #[test]
+    fn test_old_format() {
+        // Games logged before the penalty was written down used the default
+        let log = GameLog::read(&b"ratscrew-log 1\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\nend -\n"[..]).unwrap();
+        assert_eq!(log.header.penalty, Penalty::default());
+        assert_eq!(log.entries, vec![Entry::End(None)]);
+
+        // and named the rules the way version 2 of the protocol did
+        let log = GameLog::read(&b"ratscrew-log 2\nseed 1\ndeck 1 2 0\nrules Pair,SixtyNine\nplayers a b\n\
+                                   penalty burn:1\n0 play 0\nevent card-played 0 6H\n1 play 1\n\
+                                   event card-played 1 9S\n2 slap 0\nevent slapped 0 SixtyNine 0,1 6H 9S\n"[..])
+            .unwrap();
+        assert!(log.header.rules.allows(SlapPattern::Pair) && log.header.rules.allows(SlapPattern::SixtyNine));
+        assert!(!log.header.rules.allows(SlapPattern::Sandwich));
+        match log.entries[5] {
+            Entry::Event(Event::Slapped { ref found, .. }) => assert_eq!(found.pattern, SlapPattern::SixtyNine),
+            ref entry => panic!("expected a slap, got {:?}", entry),
+        }
+
+        assert_eq!(GameLog::read(&b"ratscrew-log 2\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\n"[..]),
+                   Err(LogError::NotALog));
+        assert_eq!(GameLog::read(&b"ratscrew-log 2\nseed 1\ndeck 1 2 0\nrules -\nplayers a b\npenalty fine\n"[..]),
+                   Err(LogError::BadLine(6, "bad penalty fine".to_string())));
+    }
+}
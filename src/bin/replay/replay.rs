@@ -0,0 +1,102 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Checks the log of an Egyptian RatScrew game against the engine
+
+extern crate card;
+use card::engine::{Action, Event};
+use card::replay::{Entry, GameLog};
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+const USAGE: &str = "Usage: replay FILE
+
+Plays a game log written by the server through the game again, checking
+that every action leads to what the log says it did, and lists every slap
+with when it was made.";
+
+/// Minutes, seconds and milliseconds since the deal
+fn clock(at: u64) -> String {
+    format!("{:>3}:{:02}.{:03}", at / 60_000, at / 1000 % 60, at % 1000)
+}
+
+/// Lists every slap in the log, along with the slaps that lost the race
+/// to it
+fn list_slaps(log: &GameLog) {
+    let name = |player: usize| log.header.names.get(player).map_or("?", |name| name.as_str());
+    // When the last slap that was played happened
+    let mut slapped = 0;
+    let mut entries = log.entries.iter().peekable();
+    while let Some(entry) = entries.next() {
+        match *entry {
+            Entry::Action { at, action: Action::Slap(player) } => {
+                slapped = at;
+                let outcome = match entries.peek() {
+                    Some(&&Entry::Event(Event::Slapped { ref found, .. })) => {
+                        format!("slapped a {:?} and took the pile", found.pattern)
+                    }
+                    Some(&&Entry::Event(Event::FalseSlap { .. })) => "slapped by mistake".to_string(),
+                    _ => "slapped".to_string(),
+                };
+                println!("{}  {} {}", clock(at), name(player), outcome);
+            }
+            Entry::Late { at, player } => {
+                println!("{}  {} slapped {} ms later, too late",
+                         clock(at), name(player), at.saturating_sub(slapped));
+            }
+            _ => {},
+        }
+    }
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(ref arg) if arg == "--help" || arg == "-h" => {
+            println!("{}", USAGE);
+            return;
+        }
+        Some(path) => path,
+        None => {
+            println!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let log = match File::open(&path) {
+        Ok(file) => GameLog::read(BufReader::new(file)),
+        Err(err) => {
+            println!("Can't open {}: {}", path, err);
+            process::exit(1);
+        }
+    };
+    let log = match log {
+        Ok(log) => log,
+        Err(err) => {
+            println!("{}: {}", path, err);
+            process::exit(1);
+        }
+    };
+
+    let header = &log.header;
+    println!("{} players: {}", header.names.len(), header.names.join(", "));
+    println!("Seed {}, {} deck(s) from {} up with {} joker(s) each\n",
+             header.seed, header.deck.decks, header.deck.lowest, header.deck.jokers);
+    list_slaps(&log);
+
+    match log.replay() {
+        Ok(game) => {
+            println!();
+            match game.winner() {
+                Some(winner) => println!("{} won.", header.names[winner]),
+                None => println!("The game stopped without a winner."),
+            }
+            println!("The log replays to the same game.");
+        }
+        Err(err) => {
+            println!("\nThe log doesn't replay. {}", err);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,546 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Slap rules for Egyptian RatScrew
+
+use std::fmt;
+use Card;
+use Rank::*;
+
+/// Which combinations on the pile can be slapped. Every rule is a
+/// separate switch, since house rules differ from table to table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleSet {
+    /// Top two cards have the same rank
+    pub pair: bool,
+    /// Top and third card have the same rank
+    pub sandwich: bool,
+    /// Top two cards are a 6 and a 9
+    pub sixty_nine: bool,
+    /// Top and third card are a 6 and a 9
+    pub sixty_nine_sandwich: bool,
+    /// Top three cards form a run in any order
+    pub run: bool,
+    /// Top card has the same rank as the bottom card
+    pub top_bottom: bool,
+    /// Top two cards are a King and a Queen
+    pub marriage: bool,
+    /// Top and third card are a King and a Queen
+    pub divorce: bool,
+    /// Top two cards add up to ten, Aces count as one
+    pub add_to_ten: bool,
+    /// Top card is a 10
+    pub tens: bool,
+    /// Top four cards count up or down in order
+    pub four_in_a_row: bool,
+    /// Top card is a Joker
+    pub jokers: bool,
+}
+
+impl RuleSet {
+    /// Pairs, sandwiches, sixty-nines, sixty-nine sandwiches and runs
+    /// of three, the rules the game has always been played with
+    pub fn classic() -> Self {
+        RuleSet {
+            pair: true,
+            sandwich: true,
+            sixty_nine: true,
+            sixty_nine_sandwich: true,
+            run: true,
+            top_bottom: false,
+            marriage: false,
+            divorce: false,
+            add_to_ten: false,
+            tens: false,
+            four_in_a_row: false,
+            jokers: false,
+        }
+    }
+
+    /// Every rule turned on
+    pub fn all() -> Self {
+        RuleSet {
+            pair: true,
+            sandwich: true,
+            sixty_nine: true,
+            sixty_nine_sandwich: true,
+            run: true,
+            top_bottom: true,
+            marriage: true,
+            divorce: true,
+            add_to_ten: true,
+            tens: true,
+            four_in_a_row: true,
+            jokers: true,
+        }
+    }
+
+    /// Turns the rule for a pattern on or off
+    pub fn set(&mut self, pattern: SlapPattern, on: bool) {
+        let rule = match pattern {
+            SlapPattern::Pair => &mut self.pair,
+            SlapPattern::Sandwich => &mut self.sandwich,
+            SlapPattern::SixtyNine => &mut self.sixty_nine,
+            SlapPattern::SixtyNineSandwich => &mut self.sixty_nine_sandwich,
+            SlapPattern::Run => &mut self.run,
+            SlapPattern::TopBottom => &mut self.top_bottom,
+            SlapPattern::Marriage => &mut self.marriage,
+            SlapPattern::Divorce => &mut self.divorce,
+            SlapPattern::AddToTen => &mut self.add_to_ten,
+            SlapPattern::Ten => &mut self.tens,
+            SlapPattern::FourInARow => &mut self.four_in_a_row,
+            SlapPattern::Joker => &mut self.jokers,
+        };
+        *rule = on;
+    }
+
+    /// Determines if the rule for a pattern is on
+    pub fn allows(&self, pattern: SlapPattern) -> bool {
+        match pattern {
+            SlapPattern::Pair => self.pair,
+            SlapPattern::Sandwich => self.sandwich,
+            SlapPattern::SixtyNine => self.sixty_nine,
+            SlapPattern::SixtyNineSandwich => self.sixty_nine_sandwich,
+            SlapPattern::Run => self.run,
+            SlapPattern::TopBottom => self.top_bottom,
+            SlapPattern::Marriage => self.marriage,
+            SlapPattern::Divorce => self.divorce,
+            SlapPattern::AddToTen => self.add_to_ten,
+            SlapPattern::Ten => self.tens,
+            SlapPattern::FourInARow => self.four_in_a_row,
+            SlapPattern::Joker => self.jokers,
+        }
+    }
+
+    /// Tests the top of the pile for any combination in the rule set
+    pub fn matches(&self, pile: &[Card]) -> bool {
+        self.find(pile).is_some()
+    }
+
+    /// Finds the combination on top of the pile, if the rule set has one.
+    /// Four in a row is looked for first, its top cards can make a run or
+    /// a marriage as well.
+    pub fn find(&self, pile: &[Card]) -> Option<SlapMatch> {
+        let checks: [(bool, SlapPattern, Test); 12] = [
+            (self.four_in_a_row, SlapPattern::FourInARow, is_four_in_a_row),
+            (self.pair, SlapPattern::Pair, is_pair),
+            (self.sixty_nine, SlapPattern::SixtyNine, is_sixty_nine),
+            (self.marriage, SlapPattern::Marriage, is_marriage),
+            (self.add_to_ten, SlapPattern::AddToTen, is_add_to_ten),
+            (self.sandwich, SlapPattern::Sandwich, is_sandwich),
+            (self.sixty_nine_sandwich, SlapPattern::SixtyNineSandwich, is_sixty_nine_sandwich),
+            (self.divorce, SlapPattern::Divorce, is_divorce),
+            (self.run, SlapPattern::Run, is_run),
+            (self.top_bottom, SlapPattern::TopBottom, is_top_bottom),
+            (self.tens, SlapPattern::Ten, is_ten),
+            (self.jokers, SlapPattern::Joker, is_joker),
+        ];
+
+        for &(enabled, pattern, test) in checks.iter() {
+            if enabled && pile.len() >= pattern.size() && test(pile) {
+                return Some(SlapMatch {
+                    pattern,
+                    indices: pattern.indices(pile.len()),
+                });
+            }
+        }
+        None
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::classic()
+    }
+}
+
+/// Looks for a combination on top of the pile
+type Test = fn(&[Card]) -> bool;
+
+/// A combination that can be slapped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlapPattern {
+    Pair,
+    Sandwich,
+    SixtyNine,
+    SixtyNineSandwich,
+    Run,
+    TopBottom,
+    Marriage,
+    Divorce,
+    AddToTen,
+    Ten,
+    FourInARow,
+    Joker,
+}
+
+impl SlapPattern {
+    /// Every pattern, in the order they are checked
+    pub fn all() -> [SlapPattern; 12] {
+        [SlapPattern::FourInARow, SlapPattern::Pair, SlapPattern::SixtyNine,
+         SlapPattern::Marriage, SlapPattern::AddToTen, SlapPattern::Sandwich,
+         SlapPattern::SixtyNineSandwich, SlapPattern::Divorce, SlapPattern::Run,
+         SlapPattern::TopBottom, SlapPattern::Ten, SlapPattern::Joker]
+    }
+
+    /// Name of the pattern in settings, logs and the wire protocol, the
+    /// same as its field in `RuleSet`
+    pub fn name(self) -> &'static str {
+        match self {
+            SlapPattern::Pair => "pair",
+            SlapPattern::Sandwich => "sandwich",
+            SlapPattern::SixtyNine => "sixty_nine",
+            SlapPattern::SixtyNineSandwich => "sixty_nine_sandwich",
+            SlapPattern::Run => "run",
+            SlapPattern::TopBottom => "top_bottom",
+            SlapPattern::Marriage => "marriage",
+            SlapPattern::Divorce => "divorce",
+            SlapPattern::AddToTen => "add_to_ten",
+            SlapPattern::Ten => "tens",
+            SlapPattern::FourInARow => "four_in_a_row",
+            SlapPattern::Joker => "jokers",
+        }
+    }
+
+    /// Finds a pattern by name
+    pub fn from_name(name: &str) -> Option<SlapPattern> {
+        SlapPattern::all().iter().find(|p| p.name() == name).cloned()
+    }
+
+    /// Number of cards the pile needs before the pattern can show up
+    pub fn size(self) -> usize {
+        match self {
+            SlapPattern::Ten | SlapPattern::Joker => 1,
+            SlapPattern::Pair | SlapPattern::SixtyNine | SlapPattern::Marriage
+                | SlapPattern::AddToTen | SlapPattern::TopBottom => 2,
+            SlapPattern::Sandwich | SlapPattern::SixtyNineSandwich
+                | SlapPattern::Divorce | SlapPattern::Run => 3,
+            SlapPattern::FourInARow => 4,
+        }
+    }
+
+    /// Indices of the cards making up the pattern in a pile of `len` cards
+    fn indices(self, len: usize) -> Vec<usize> {
+        match self {
+            SlapPattern::TopBottom => vec![0, len - 1],
+            _ => (len - self.size()..len).collect(),
+        }
+    }
+}
+
+impl fmt::Display for SlapPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            SlapPattern::Pair => "Pair",
+            SlapPattern::Sandwich => "Sandwich",
+            SlapPattern::SixtyNine => "Sixty-nine",
+            SlapPattern::SixtyNineSandwich => "Sixty-nine sandwich",
+            SlapPattern::Run => "Run",
+            SlapPattern::TopBottom => "Top and bottom",
+            SlapPattern::Marriage => "Marriage",
+            SlapPattern::Divorce => "Divorce",
+            SlapPattern::AddToTen => "Add to ten",
+            SlapPattern::Ten => "Ten",
+            SlapPattern::FourInARow => "Four in a row",
+            SlapPattern::Joker => "Joker",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A combination found on the pile
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlapMatch {
+    pub pattern: SlapPattern,
+    /// Indices into the pile of the cards in the combination, bottom
+    /// to top. Sandwiches include the card in the middle.
+    pub indices: Vec<usize>,
+}
+
+impl SlapMatch {
+    /// The cards of the combination
+    pub fn cards(&self, pile: &[Card]) -> Vec<Card> {
+        self.indices.iter().map(|&i| pile[i]).collect()
+    }
+}
+
+/// Top and second card have same rank
+fn is_pair(pile: &[Card]) -> bool {
+    pile[pile.len() - 1] == pile[pile.len() - 2]
+}
+
+/// Top and third card have same rank
+fn is_sandwich(pile: &[Card]) -> bool {
+    pile[pile.len() - 1] == pile[pile.len() - 3]
+}
+
+/// Checks if left and right cards form (6, 9) pairing
+fn is_sixty_nine_match(left: Card, right: Card) -> bool {
+    if left == 6 && right == 9 {
+        return true;
+    }
+    if right == 6 && left == 9 {
+        return true;
+    }
+    false
+}
+
+/// Top card and second card have ranks of 6 && 9 or 9 && 6
+fn is_sixty_nine(pile: &[Card]) -> bool {
+    is_sixty_nine_match(pile[pile.len() - 1], pile[pile.len() - 2])
+}
+
+/// Top card and third card have ranks 6 && 9 or 9 && 6
+fn is_sixty_nine_sandwich(pile: &[Card]) -> bool {
+    is_sixty_nine_match(pile[pile.len() - 1], pile[pile.len() - 3])
+}
+
+/// Determines if any of the three cards form a pair
+/// returns true if their is a pair
+fn find_pair_run(left: Card, middle: Card, right: Card) -> bool {
+    if left == middle || left == right || middle == right {
+        return true;
+    }
+    false
+}
+
+/// Determines if the ranks of cards differ by one
+fn find_abs(left: Card, right: Card) -> bool {
+    // If left is an ace, compare it as a 1 and a 14 and return true if
+    // cards are sequential
+    if is_ace(left) {
+        let value_low = left.rank.value() as i32 - right.rank.value() as i32;
+        let value_high = 14 - right.rank.value() as i32;
+        if value_low.abs() == 1 || value_high.abs() == 1 {
+            return true;
+        }
+    }
+    // If right is an ace, compare it as a 1 and a 14 and return true if 
+    // cards are sequential
+    if is_ace(right) {
+        let value_low = left.rank.value() as i32 - right.rank.value() as i32;
+        let value_high = left.rank.value() as i32 - 14;
+        if value_low.abs() == 1 || value_high.abs() == 1 {
+            return true;
+        }
+    }
+    // Otherwise just check for sequentail cards
+    let value = left.rank.value() as i32 - right.rank.value() as i32;
+    if value.abs() == 1 {
+        return true;
+    }
+    false
+}
+
+/// Check a card for an ace value
+fn is_ace(card: Card) -> bool {
+    if card.rank.value() == 1 {
+        return true;
+    }
+    false
+}
+
+/// Top three cards form a run in any order
+fn is_run(pile: &[Card]) -> bool {
+    let left = pile[pile.len() - 1];
+    let middle = pile[pile.len() - 2];
+    let right = pile[pile.len() - 3];
+
+    // Jokers don't have a place in a run
+    if left.rank == Joker || middle.rank == Joker || right.rank == Joker {
+        return false;
+    }
+
+    // If no cards have equal rank, search for a run
+    if !find_pair_run(left, middle, right) {
+        let one_two = find_abs(left, middle);
+        let one_three = find_abs(left, right);
+        let two_three = find_abs(middle, right);
+
+        // If the first and second cards differ by one rank
+        // and the second and third cards differ by one rank
+        // (e.g. 3->4->5)
+        if one_two && two_three {
+            return true;
+        }
+
+        // If the first and third cards differ by one rank
+        // and the second and third cards differ by one rank
+        // (e.g. 3->5->4)
+        if one_three && two_three {
+            return true;
+        }
+
+        // If the first and second cards differ by one rank
+        // and the first and third card differ by one rank
+        // (e.g. 4->5->3)
+        if one_two && one_three {
+            return true;
+        }
+    }
+    false
+}
+
+/// Top card has the same rank as the bottom card of the pile
+fn is_top_bottom(pile: &[Card]) -> bool {
+    pile[pile.len() - 1] == pile[0]
+}
+
+/// Checks if left and right cards are a King and a Queen
+fn is_king_queen(left: Card, right: Card) -> bool {
+    matches!((left.rank, right.rank), (King, Queen) | (Queen, King))
+}
+
+/// Top card and second card are a King and a Queen
+fn is_marriage(pile: &[Card]) -> bool {
+    is_king_queen(pile[pile.len() - 1], pile[pile.len() - 2])
+}
+
+/// Top card and third card are a King and a Queen
+fn is_divorce(pile: &[Card]) -> bool {
+    is_king_queen(pile[pile.len() - 1], pile[pile.len() - 3])
+}
+
+/// Top two cards are number cards or Aces adding up to ten
+fn is_add_to_ten(pile: &[Card]) -> bool {
+    let left = pile[pile.len() - 1];
+    let right = pile[pile.len() - 2];
+    if left.rank == Joker || right.rank == Joker {
+        return false;
+    }
+    left.rank.value() + right.rank.value() == 10
+}
+
+/// Top card is a 10
+fn is_ten(pile: &[Card]) -> bool {
+    pile[pile.len() - 1] == 10
+}
+
+/// Top card is a Joker
+fn is_joker(pile: &[Card]) -> bool {
+    pile[pile.len() - 1].rank == Joker
+}
+
+/// Checks if the values count up or down by one from card to card
+fn is_sequence(values: &[i32]) -> bool {
+    let up = values.windows(2).all(|w| w[1] - w[0] == 1);
+    let down = values.windows(2).all(|w| w[0] - w[1] == 1);
+    up || down
+}
+
+/// Top four cards count up or down in the order they were laid,
+/// Aces may be low or high
+fn is_four_in_a_row(pile: &[Card]) -> bool {
+    let top = &pile[pile.len() - 4..];
+    if top.iter().any(|c| c.rank == Joker) {
+        return false;
+    }
+    let low: Vec<i32> = top.iter().map(|c| c.rank.value() as i32).collect();
+    let high: Vec<i32> = top.iter()
+        .map(|c| if is_ace(*c) { 14 } else { c.rank.value() as i32 })
+        .collect();
+    is_sequence(&low) || is_sequence(&high)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Rank, Suit::*};
+
+    fn pile(ranks: &[Rank]) -> Vec<Card> {
+        ranks.iter().map(|r| Card::new(*r, Clubs)).collect()
+    }
+
+    #[test]
+    fn test_classic_combinations() {
+        let rules = RuleSet::classic();
+
+        assert!(rules.matches(&pile(&[Num(4), Num(4)])));
+        assert!(rules.matches(&pile(&[Num(4), King, Num(4)])));
+        assert!(rules.matches(&pile(&[Num(9), Num(6)])));
+        assert!(rules.matches(&pile(&[Num(6), Num(2), Num(9)])));
+        assert!(rules.matches(&pile(&[Queen, Ace, King])));
+        assert!(!rules.matches(&pile(&[Num(4)])));
+        assert!(!rules.matches(&pile(&[Num(2), Num(4), Num(8)])));
+        assert!(!rules.matches(&pile(&[Num(2), Joker, Ace])));
+    }
+
+    #[test]
+    fn test_rules_switched_off() {
+        let mut rules = RuleSet::classic();
+        rules.pair = false;
+        rules.run = false;
+
+        assert!(!rules.matches(&pile(&[Num(4), Num(4)])));
+        assert!(!rules.matches(&pile(&[Num(3), Num(4), Num(5)])));
+        assert!(rules.matches(&pile(&[Num(4), Num(2), Num(4)])));
+
+        rules.set(SlapPattern::Marriage, true);
+        assert!(rules.matches(&pile(&[King, Queen])));
+        assert!(rules.allows(SlapPattern::Marriage) && !rules.allows(SlapPattern::Run));
+    }
+
+    #[test]
+    fn test_find_pattern() {
+        let rules = RuleSet::all();
+        let cards = pile(&[Num(2), Num(7), King, Num(7)]);
+        let found = rules.find(&cards).unwrap();
+
+        assert_eq!(found.pattern, SlapPattern::Sandwich);
+        assert_eq!(found.indices, vec![1, 2, 3]);
+        assert_eq!(found.cards(&cards)[0].rank, Num(7));
+
+        let found = rules.find(&pile(&[Num(5), Num(2), Num(3), Num(5)])).unwrap();
+        assert_eq!(found.pattern, SlapPattern::TopBottom);
+        assert_eq!(found.indices, vec![0, 3]);
+
+        let found = rules.find(&pile(&[Num(9), Num(3), Num(4), Num(5)])).unwrap();
+        assert_eq!(found.pattern, SlapPattern::Run);
+        assert_eq!(rules.find(&pile(&[Num(2), Num(9), Num(4)])), None);
+    }
+
+    #[test]
+    fn test_four_in_a_row_first() {
+        // Four in a row and a run, with both rules on
+        let rules = RuleSet::all();
+        let found = rules.find(&pile(&[Num(2), Num(3), Num(4), Num(5)])).unwrap();
+        assert_eq!(found.pattern, SlapPattern::FourInARow);
+        assert_eq!(found.indices, vec![0, 1, 2, 3]);
+
+        // And a marriage on top
+        let found = rules.find(&pile(&[Num(10), Jack, Queen, King])).unwrap();
+        assert_eq!(found.pattern, SlapPattern::FourInARow);
+
+        // Without the rule it's only a run
+        let mut rules = RuleSet::all();
+        rules.set(SlapPattern::FourInARow, false);
+        let found = rules.find(&pile(&[Num(2), Num(3), Num(4), Num(5)])).unwrap();
+        assert_eq!(found.pattern, SlapPattern::Run);
+    }
+
+    #[test]
+    fn test_pattern_names() {
+        for &pattern in SlapPattern::all().iter() {
+            assert_eq!(SlapPattern::from_name(pattern.name()), Some(pattern));
+        }
+        assert_eq!(SlapPattern::from_name("sixty_nine"), Some(SlapPattern::SixtyNine));
+        assert_eq!(SlapPattern::from_name("SixtyNine"), None);
+    }
+
+    #[test]
+    fn test_optional_combinations() {
+        let rules = RuleSet::all();
+
+        assert!(rules.matches(&pile(&[Num(8), Num(2), Num(5), Num(8)])));
+        assert!(rules.matches(&pile(&[Queen, King])));
+        assert!(rules.matches(&pile(&[King, Num(2), Queen])));
+        assert!(rules.matches(&pile(&[Num(3), Num(7)])));
+        assert!(rules.matches(&pile(&[Ace, Num(9)])));
+        assert!(rules.matches(&pile(&[Num(3), Num(10)])));
+        assert!(rules.matches(&pile(&[Num(5), Joker])));
+        assert!(rules.matches(&pile(&[Jack, Queen, King, Ace])));
+        assert!(rules.matches(&pile(&[Num(5), Num(4), Num(3), Num(2)])));
+        assert!(!rules.matches(&pile(&[Num(2), Num(5), Num(4), Num(8)])));
+        assert!(!RuleSet::classic().matches(&pile(&[Queen, King])));
+    }
+}
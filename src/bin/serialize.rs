@@ -0,0 +1,201 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Serde support for the card types, built with the serde feature
+
+//! Serde support for cards and decks.
+//!
+//! Cards are written the short way the protocol and the game logs write
+//! them: a card is the string "10H", a rank "10" and a suit "H", so a pile
+//! is a list of short strings. Cards read back from any form `Card`'s
+//! `FromStr` takes. Decks, hands and piles are lists of cards from the
+//! bottom up, the same order as their `cards()`. A `DeckSpec` is a list of
+//! its three numbers: decks, lowest and jokers, in that order, and only
+//! reads back if it makes a deck `DeckSpec`'s builders would.
+
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Display;
+use std::str::FromStr;
+use render;
+use {Card, Deck, DeckSpec, Hand, Pile, Rank, Suit};
+use deck::{MAX_DECKS, MAX_JOKERS};
+
+impl Serialize for Card {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&render::code(*self))
+    }
+}
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Card, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for Rank {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // The code of any card of the rank, less its suit
+        let code = render::code(Card::new(*self, Suit::Hearts));
+        serializer.serialize_str(&code[..code.len() - 1])
+    }
+}
+
+impl<'de> Deserialize<'de> for Rank {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rank, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for Suit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let code = render::code(Card::new(Rank::Ace, *self));
+        serializer.serialize_str(&code[1..])
+    }
+}
+
+impl<'de> Deserialize<'de> for Suit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Suit, D::Error> {
+        from_text(deserializer)
+    }
+}
+
+impl Serialize for DeckSpec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        [self.decks, self.lowest, self.jokers][..].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for DeckSpec {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DeckSpec, D::Error> {
+        let numbers: Vec<u32> = Deserialize::deserialize(deserializer)?;
+        match numbers[..] {
+            [decks, _, _] if !(1..=MAX_DECKS).contains(&decks) => {
+                let expected = format!("from 1 to {} decks", MAX_DECKS);
+                Err(D::Error::invalid_value(Unexpected::Unsigned(decks.into()), &expected.as_str()))
+            }
+            [_, lowest, _] if !(2..=10).contains(&lowest) => {
+                Err(D::Error::invalid_value(Unexpected::Unsigned(lowest.into()), &"a number card from 2 to 10"))
+            }
+            [_, _, jokers] if jokers > MAX_JOKERS => {
+                let expected = format!("at most {} jokers", MAX_JOKERS);
+                Err(D::Error::invalid_value(Unexpected::Unsigned(jokers.into()), &expected.as_str()))
+            }
+            [decks, lowest, jokers] => Ok(DeckSpec::standard().decks(decks).stripped(lowest).jokers(jokers)),
+            _ => Err(D::Error::invalid_length(numbers.len(), &"decks, lowest and jokers")),
+        }
+    }
+}
+
+impl Serialize for Deck {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Deck {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Deck, D::Error> {
+        Vec::<Card>::deserialize(deserializer).map(Deck::from)
+    }
+}
+
+impl Serialize for Hand {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hand {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hand, D::Error> {
+        Vec::<Card>::deserialize(deserializer).map(Hand::from)
+    }
+}
+
+impl Serialize for Pile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.cards().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Pile {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Pile, D::Error> {
+        Vec::<Card>::deserialize(deserializer).map(Pile::from)
+    }
+}
+
+/// Reads a string and parses it
+fn from_text<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where D: Deserializer<'de>, T: FromStr, T::Err: Display
+{
+    let text = String::deserialize(deserializer)?;
+    text.parse().map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use toml::Value;
+    use {Card, Deck, DeckSpec, Pile};
+    use {Rank::*, Suit::*};
+
+    #[test]
+    fn test_compact() {
+        let pile = Pile::from(vec![Card::new(Num(10), Hearts), Card::new(Queen, Spades),
+                                   Card::new(Joker, Diamonds)]);
+        let value = Value::try_from(&pile).unwrap();
+        assert_eq!(value.to_string(), r#"["10H", "QS", "XD"]"#);
+        assert_eq!(Value::try_from(Ace).unwrap(), Value::String("A".to_string()));
+        assert_eq!(Value::try_from(Clubs).unwrap(), Value::String("C".to_string()));
+
+        let spec = DeckSpec { decks: 2, lowest: 6, jokers: 1 };
+        assert_eq!(Value::try_from(spec).unwrap().to_string(), "[2, 6, 1]");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let deck = DeckSpec { decks: 1, lowest: 2, jokers: 2 }.build();
+        let back: Deck = Value::try_from(&deck).unwrap().try_into().unwrap();
+        for (card, read) in deck.iter().zip(back.iter()) {
+            assert_eq!((card.rank, card.suit), (read.rank, read.suit));
+        }
+        assert_eq!(back.len(), deck.len());
+
+        let spec: DeckSpec = Value::try_from(DeckSpec::standard()).unwrap().try_into().unwrap();
+        assert_eq!(spec, DeckSpec::standard());
+
+        // Any form a card is written in reads back
+        let card: Card = Value::String("Queen of Hearts".to_string()).try_into().unwrap();
+        assert_eq!((card.rank, card.suit), (Queen, Hearts));
+        assert!(Value::String("QZ".to_string()).try_into::<Card>().is_err());
+    }
+
+    // Reads a deck spec from a list of numbers
+    fn spec(numbers: &[i64]) -> Result<DeckSpec, ::toml::de::Error> {
+        Value::Array(numbers.iter().map(|&n| Value::Integer(n)).collect()).try_into()
+    }
+
+    #[test]
+    fn test_spec_decks() {
+        assert_eq!(spec(&[2, 6, 1]).unwrap(), DeckSpec { decks: 2, lowest: 6, jokers: 1 });
+        for &decks in [0, 4_000_000_000].iter() {
+            let err = spec(&[decks, 2, 0]).unwrap_err().to_string();
+            assert!(err.contains("from 1 to 100 decks"), "{}", err);
+        }
+        assert!(spec(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_spec_too_many_jokers() {
+        let err = spec(&[1, 2, 4_000_000_000]).unwrap_err().to_string();
+        assert!(err.contains("at most 100 jokers"), "{}", err);
+        assert_eq!(spec(&[1, 2, 100]).unwrap(), DeckSpec::standard().jokers(100));
+    }
+
+    #[test]
+    fn test_spec_lowest_card() {
+        for &lowest in [0, 1, 11, 14].iter() {
+            let err = spec(&[1, lowest, 0]).unwrap_err().to_string();
+            assert!(err.contains("a number card from 2 to 10"), "{}", err);
+        }
+        assert_eq!(spec(&[1, 10, 0]).unwrap(), DeckSpec::standard().stripped(10));
+    }
+}
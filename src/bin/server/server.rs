@@ -1,657 +1,1202 @@
 // MIT License
 // Copyright (c) 2018 Cole Phares
-// Last Modified: 6/10/2018
+// Last Modified: 10/18/2026
 // Server side for online card game Egyptian RatScrew
 
 extern crate card;
 extern crate rand;
-use card::{Card, Rank::*, Suit::*};
-use rand::{Rng, random};
-use std::net::{TcpListener, SocketAddr, TcpStream};
-use std::io::{BufReader, Write, BufRead, Error, ErrorKind};
-use std::time::Duration;
+use card::{GameRng, random_seed, seeded};
+use card::bot::{Bot, Decision, Registry, View};
+use card::config::{self, ConfigError, ServerConfig};
+use card::engine::{Action, Event, GameState, MAX_PLAYERS};
+use card::protocol::{self, ClientMessage, Feature, HandshakeError, ServerMessage};
+use card::replay::{Header, Recorder};
+use rand::Rng;
+use std::collections::VecDeque;
+use std::env;
+use std::fs::{self, File};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::io::{self, BufReader, Write, BufRead, Error, ErrorKind};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::process::{self, Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Milliseconds a slap can come in behind the one that won and still
+/// count as part of the same race
+const SLAP_GRACE: u64 = 250;
+
+/// Cards from the top of the pile sent to the clients
+const PILE_TOP: usize = 5;
+
+/// Seconds a bot program gets to say hello once it starts
+const BOT_HELLO: u64 = 5;
+
+/// Trait for a Player sitting at the table
+trait Player: Send {
+    // Asks the player to play a card. Players who type their moves
+    // answer later through read_action.
+    fn play_card(&mut self, game: &GameState) -> Option<Action>;
+
+    // Turns a message from the player's client into an action
+    fn read_action(&mut self, game: &GameState, message: &ClientMessage) -> Option<Action>;
+
+    // How long the player takes to slap the pile after a card hits it,
+    // None if they leave it alone or type their slaps
+    fn slap_check(&mut self, game: &GameState) -> Option<Duration>;
+
+    // Tells the player the cards are dealt
+    fn seated(&mut self, game: &GameState);
+
+    // Tells the player about something that happened in the game
+    fn notify(&mut self, game: &GameState, event: &Event);
+
+    // Sends a message from the server that isn't part of the game
+    fn tell(&mut self, message: &str);
+}
 
-/// Contains the players hand and side pile
-struct PlayerState {
-    hand: Vec<Card>,
-    side_pile: Vec<Card>,
+/// Where the messages for a player who speaks the protocol go
+trait Connection: Write + Send {
+    // Ends the conversation once the game is over
+    fn hang_up(&mut self);
 }
 
-/// Creates a PlayerState
-impl PlayerState {
-    fn new() -> Self {
-        PlayerState {
-            hand: Vec::new(),
-            side_pile: Vec::new(),
-        }
+impl Connection for TcpStream {
+    fn hang_up(&mut self) {
+        self.shutdown(Shutdown::Both).ok();
     }
 }
 
-/// Trait for a Player
-trait Player {
-    // Adds a single card to the hand
-    // Used when dealing
-    fn add_to_hand(&mut self, Card) ->
-        Result<(), Error>;
+/// A bot running as its own program, speaking the protocol over its
+/// stdin and stdout
+struct BotProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
 
-    // Adds a vector of cards to the side pile
-    fn add_to_side_pile(&mut self, &mut Vec<Card>) ->
-        Result<(), Error>;
+impl Write for BotProcess {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.stdin {
+            Some(ref mut stdin) => stdin.write(buf),
+            None => Err(Error::new(ErrorKind::BrokenPipe, "The bot has been stopped")),
+        }
+    }
 
-    // Adds the side pile to the hand when hand is empty
-    fn add_side_pile_to_hand(&mut self) -> Result<(), Error>;
+    fn flush(&mut self) -> io::Result<()> {
+        match self.stdin {
+            Some(ref mut stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+    }
+}
 
-    // Plays a card from the players hand
-    fn play_card(&mut self, &mut Vec<Card>, &mut BufRead, &mut Write, &mut Player) -> 
-        Result<Vec<Card>, Error>;
+impl Connection for BotProcess {
+    // Closes the bot's stdin and stops it
+    fn hang_up(&mut self) {
+        self.stdin.take();
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
 
-    // Check for slap
-    fn slap_check(&mut self, &mut Vec<Card>, &TcpStream, &mut Player) -> 
-        Result<Vec<Card>, Error>;
+/// A player who speaks the protocol, either a client connected over TCP
+/// or a bot program
+struct RemotePlayer {
+    seat: usize,
+    names: Vec<String>,
+    writer: Box<dyn Connection>,
+}
 
-    // Determines if a player has all the cards
-    fn won(&mut self, &mut Write) -> bool;
+impl RemotePlayer {
+    fn new(seat: usize, names: Vec<String>, writer: Box<dyn Connection>) -> Self {
+        RemotePlayer {
+            seat,
+            names,
+            writer,
+        }
+    }
 
-    // Makes the PlayerState read only visible
-    fn state(&self) -> &PlayerState;
+    // Sends a message to the client
+    fn send(&mut self, message: &ServerMessage) {
+        send(&mut *self.writer, message);
+    }
 }
 
-/// The player
-struct HumanPlayer(PlayerState);
+/// Hangs up on the client once the game is over
+impl Drop for RemotePlayer {
+    fn drop(&mut self) {
+        self.writer.hang_up();
+    }
+}
 
 /// Player trait implementation for player
-impl Player for HumanPlayer {
-    // Adds a single card to the hand
-    // Used when dealing
-    fn add_to_hand(&mut self, card: Card) ->
-        Result<(), Error> {
-            self.0.hand.push(card);
-            Ok(())
+impl Player for RemotePlayer {
+    // Asks the client to play a card
+    fn play_card(&mut self, _game: &GameState) -> Option<Action> {
+        self.send(&ServerMessage::YourTurn);
+        None
     }
 
-    // Adds a vector of cards to the side pile
-    fn add_to_side_pile(&mut self, pile: &mut Vec<Card>) ->
-        Result<(), Error> {
-        for _ in 0..pile.len() {
-            self.0.side_pile.push(pile.pop().unwrap());
+    // Turns a message from the client into an action
+    fn read_action(&mut self, game: &GameState, message: &ClientMessage) -> Option<Action> {
+        match *message {
+            ClientMessage::PlayCard => Some(Action::PlayCard(self.seat)),
+            ClientMessage::Slap if !game.can_slap(self.seat) => {
+                self.send(&ServerMessage::CantSlap);
+                None
+            }
+            ClientMessage::Slap => Some(Action::Slap(self.seat)),
+            ClientMessage::Quit => Some(Action::Quit(self.seat)),
+            ClientMessage::Hello { .. } | ClientMessage::Deal => None,
         }
-        Ok(())
     }
 
-    // Adds the side pile to the hand when hand is empty
-    fn add_side_pile_to_hand(&mut self) -> Result<(), Error> {
-        // If side pile is empty, then player loses
-        if self.0.side_pile.is_empty() {
-            return Err(Error::new(ErrorKind::Other, "Your hand is empty. Computer wins!!"));
-        }
-        self.0.side_pile = shuffle_deck(self.0.side_pile.to_vec());
-        for _ in 0..self.0.side_pile.len() {
-            self.0.hand.push(self.0.side_pile.pop().unwrap());
-        }
-        Ok(())
+    // The client slaps by pressing space
+    fn slap_check(&mut self, _game: &GameState) -> Option<Duration> {
+        None
     }
 
-    // Plays a card from the players hand
-    fn play_card(&mut self, pile: &mut Vec<Card>, reader: &mut BufRead, 
-        mut writer: &mut Write, _opponent: &mut Player) -> Result<Vec<Card>, Error>
-    {
-        // Read input from player
-        loop {
-            writeln!(writer, "Your turn! Press c to play card\r\n").ok();
-            writer.flush().ok();
-            
-            let mut response = String::new();
-            reader.read_line(&mut response).ok();
-
-            match response.trim() {
-                "c" => {
-                    // If humans hand is empty, check for side_pile
-                    if self.0.hand.is_empty() {
-                        // Shuffle and add side_pile to hand
-                        match self.add_side_pile_to_hand() {
-                            Ok(()) => {},
-                            Err(err) => return Err(err),
-                        }
-                    }
-                    // Add card to pile
-                    let card = self.0.hand.pop().unwrap();
-                    pile.push(card);
-                    break;
-                }
-                "q" => return Err(Error::new(ErrorKind::Other, "Player quits!")),
-                _ => {
-                    writeln!(writer, "Invalid key! Press c to play card\r\n").ok();
-                    writer.flush().ok();
-                },
-            };
-        }
+    // Lets the client know where they sit, who plays first and how many
+    // cards everyone was dealt
+    fn seated(&mut self, game: &GameState) {
+        let message = ServerMessage::Seated {
+            seat: self.seat,
+            first: game.turn(),
+            names: self.names.clone(),
+        };
+        self.send(&message);
+        self.send(&hands(game));
+    }
 
-        // Send updated top three cards of the pile to client
-        send_pile(&pile, &mut writer);
-        
-        Ok(pile.to_vec())
-    }
-
-    // Check for slap
-    fn slap_check(&mut self, mut pile: &mut Vec<Card>, socket: &TcpStream, 
-        opponent: &mut Player) -> Result<Vec<Card>, Error> {
-        
-        // Edit the time computer waits for input
-        let rand = rand::thread_rng().gen_range(1, 3);
-        socket.set_read_timeout(Some(Duration::new(rand, 0))).ok();
-        let mut reader = BufReader::new(socket);
-        let mut writer = socket.try_clone().unwrap();
-        let default_time = Duration::new(3, 0);
-
-        // Wait for response from player
-        let mut response = String::new();
-        reader.read_line(&mut response).ok();
-        
-        // Determine response action
-        match response.trim() {
-            // If player thinks there's a combination
-            "space" => {
-                // Check if there's a combination and add pile to
-                // HumanPlayer's hand, otherwise add pile to 
-                // MachinePlayer's pile
-                if test_pile(&pile) {
-                    writeln!(writer, "Combination found. You won the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    self.add_to_side_pile(&mut pile).ok();
-                } else {
-                    writeln!(writer, "No combination. Computer gets the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    opponent.add_to_side_pile(&mut pile).ok();
-                }
-                socket.set_read_timeout(Some(default_time)).ok();
-                return Ok(pile.to_vec());
-            },
-            "q" => return Err(Error::new(ErrorKind::Other, "Player quits!")),
-            _ => {
-                if test_pile(&pile) {
-                    writeln!(writer, "Combination found. Computer gets the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    opponent.add_to_side_pile(&mut pile).ok();
-                    socket.set_read_timeout(Some(default_time)).ok();
-                    return Ok(pile.to_vec());
-                }
+    // Passes the event on to the client, along with the pile whenever
+    // cards are added to it and the players' cards whenever they change
+    fn notify(&mut self, game: &GameState, event: &Event) {
+        self.send(&ServerMessage::Event(event.clone()));
+        match *event {
+            Event::CardPlayed { .. } | Event::Burned { .. } => {
+                let pile = game.pile();
+                self.send(&ServerMessage::Pile { size: pile.len(), top: pile.top(PILE_TOP).to_vec() });
+                self.send(&hands(game));
             }
-        };
-        // Ignore any other incoming key events
-        let time = 3 - rand;
-        socket.set_read_timeout(Some(Duration::new(time, 0))).ok();
-        reader.read_line(&mut response).ok();
-        match response.trim() {
+            Event::PileTaken { .. } => self.send(&hands(game)),
             _ => {},
         }
-        socket.set_read_timeout(Some(default_time)).ok();
-        
-        Ok(pile.to_vec())
-    }
-    
-    // Determines if a player has all the cards
-    fn won(&mut self, writer: &mut Write) -> bool {
-        if self.0.hand.len() == 52 {
-            writeln!(writer, "Congratulations!! You won the game!!\r\n").ok();
-            writer.flush().ok();
-            return true;
-        }
-        false
     }
 
-    // Makes the PlayerState read only visible
-    fn state(&self) -> &PlayerState {
-        &self.0
+    fn tell(&mut self, message: &str) {
+        self.send(&ServerMessage::Notice(message.to_string()));
     }
 }
 
-/// The machine player
-struct MachinePlayer(PlayerState);
+/// A bot sitting at the table
+struct BotPlayer {
+    seat: usize,
+    bot: Box<dyn Bot>,
+    // Slap the bot decided on since the last card was played
+    slap: Option<Duration>,
+    rng: GameRng,
+}
+
+/// Player trait implementation for bots
+impl Player for BotPlayer {
+    // Bots play their card as soon as they're asked
+    fn play_card(&mut self, game: &GameState) -> Option<Action> {
+        self.bot.turn(&View::new(game, self.seat));
+        Some(Action::PlayCard(self.seat))
+    }
 
-/// Player trait implementation for machine player
-impl Player for MachinePlayer {
-    // Adds a single card to the hand
-    // Used when dealing
-    fn add_to_hand(&mut self, card: Card) ->
-        Result<(), Error> {
-            self.0.hand.push(card);
-            Ok(())
+    // Bots don't have a client
+    fn read_action(&mut self, _game: &GameState, _message: &ClientMessage) -> Option<Action> {
+        None
     }
-    
-    // Adds a vector of cards to the side pile
-    fn add_to_side_pile(&mut self, pile: &mut Vec<Card>) ->
-        Result<(), Error> {
-        for _ in 0..pile.len() {
-            self.0.side_pile.push(pile.pop().unwrap());
+
+    fn slap_check(&mut self, _game: &GameState) -> Option<Duration> {
+        self.slap.take()
+    }
+
+    fn seated(&mut self, game: &GameState) {
+        self.bot.seated(&View::new(game, self.seat));
+    }
+
+    // Passes the event on to the bot and remembers if it wants to slap
+    fn notify(&mut self, game: &GameState, event: &Event) {
+        if let Event::CardPlayed { .. } = *event {
+            self.slap = None;
+        }
+        match self.bot.observe(&View::new(game, self.seat), event, &mut self.rng) {
+            Decision::Slap => self.slap = Some(Duration::from_millis(0)),
+            Decision::SlapAfter(delay) => self.slap = Some(delay),
+            Decision::Pass => {},
         }
-        Ok(())
     }
 
-    // Adds the side pile to the hand when hand is empty
-    fn add_side_pile_to_hand(&mut self) -> Result<(), Error> {
-        // If side pile is empty, then computer loses
-        if self.0.side_pile.is_empty() {
-            return Err(Error::new(ErrorKind::Other, "Computers hand is empty. You win!!"));
+    fn tell(&mut self, _message: &str) {}
+}
+
+/// Cards every player holds, for the clients to show
+fn hands(game: &GameState) -> ServerMessage {
+    let counts = (0..game.num_players())
+        .map(|player| game.player(player))
+        .map(|state| (state.hand().len(), state.side_pile().len()))
+        .collect();
+    ServerMessage::Hands { counts }
+}
+
+/// Sends a message to a client, one line per message
+fn send<T: ?Sized + Write>(writer: &mut T, message: &ServerMessage) {
+    write!(writer, "{}\r\n", message.encode()).ok();
+    writer.flush().ok();
+}
+
+/// A message from one of the clients, stamped with the moment it
+/// arrived at the server
+struct Input {
+    client: usize,
+    message: ClientMessage,
+    at: Instant,
+}
+
+/// Forwards every message the client sends to the table. Lines that
+/// aren't messages are dropped, a closed connection counts as quitting.
+fn spawn_reader<R>(client: usize, reader: R, inputs: Sender<Input>) where R: BufRead + Send + 'static {
+    thread::spawn(move || {
+        for line in reader.lines() {
+            let message = match line {
+                Ok(line) => match ClientMessage::decode(&line) {
+                    Ok(message) => message,
+                    Err(_) => continue,
+                },
+                Err(_) => break,
+            };
+            let input = Input { client, message, at: Instant::now() };
+            if inputs.send(input).is_err() {
+                return;
+            }
         }
-        self.0.side_pile = shuffle_deck(self.0.side_pile.to_vec());
-        for _ in 0..self.0.side_pile.len() {
-            self.0.hand.push(self.0.side_pile.pop().unwrap());
+        inputs.send(Input { client, message: ClientMessage::Quit, at: Instant::now() }).ok();
+    });
+}
+
+/// Starts a bot program and waits for it to say hello on its stdout,
+/// like any client would
+fn start_bot(command: &str) -> Result<(BotProcess, BufReader<ChildStdout>), Error> {
+    let mut words = command.split_whitespace();
+    let program = words.next().ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Empty bot command"))?;
+    let mut child = Command::new(program).args(words)
+                                         .stdin(Stdio::piped())
+                                         .stdout(Stdio::piped())
+                                         .spawn()?;
+    let stdout = child.stdout.take();
+    let stdin = child.stdin.take();
+    let mut bot = BotProcess { child, stdin };
+    let stdout = match stdout {
+        Some(stdout) => stdout,
+        None => {
+            bot.hang_up();
+            return Err(Error::new(ErrorKind::BrokenPipe, "Can't read from the bot"));
+        }
+    };
+
+    // Read the hello on another thread so a silent bot can't hold up
+    // the table
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stdout);
+        let mut line = String::new();
+        let read = reader.read_line(&mut line);
+        sender.send((read.map(|_| line), reader)).ok();
+    });
+    let (line, reader) = match receiver.recv_timeout(Duration::from_secs(BOT_HELLO)) {
+        Ok(read) => read,
+        Err(_) => {
+            bot.hang_up();
+            return Err(Error::new(ErrorKind::TimedOut, "The bot didn't say hello"));
+        }
+    };
+
+    let welcome = match line.ok().and_then(|line| ClientMessage::decode(&line).ok()) {
+        Some(ClientMessage::Hello { version, ref features, .. }) => {
+            protocol::negotiate(version, features, &FEATURES)
+        }
+        _ => Err(HandshakeError::NoHello),
+    };
+    match welcome {
+        Ok(welcome) => {
+            send(&mut bot, &welcome);
+            Ok((bot, reader))
+        }
+        Err(err) => {
+            send(&mut bot, &ServerMessage::Rejected { reason: err.to_string() });
+            bot.hang_up();
+            Err(Error::new(ErrorKind::InvalidData, err))
         }
-        Ok(())
     }
-    
-    // Plays a card from the players hand
-    fn play_card(&mut self, pile: &mut Vec<Card>, _reader: &mut BufRead, 
-        mut writer: &mut Write, _opponent: &mut Player) -> Result<Vec<Card>, Error>
-    {
-        writeln!(writer, "Computer's turn!\r\n").ok();
-        writer.flush().ok();
-        
-        // Check if hand is empty
-        // Refill with side pile if it is
-        if self.0.hand.is_empty() {
-            match self.add_side_pile_to_hand() {
-                Ok(()) => {},
-                Err(err) => return Err(err),
+}
+
+/// An action a client asked for and when they asked for it
+struct Move {
+    client: usize,
+    action: Action,
+    at: Instant,
+}
+
+/// A game in progress and the players sitting around it
+struct Table {
+    // Seed the game was dealt from, to play it again
+    seed: u64,
+    // Where the game is logged, None if it isn't
+    log: Option<Recorder<File>>,
+    log_path: Option<PathBuf>,
+    game: GameState,
+    players: Vec<Box<dyn Player>>,
+    inputs: Receiver<Input>,
+    // Seat of every client that joined the lobby, None once they leave
+    seats: Vec<Option<usize>>,
+    shutdown: Arc<AtomicBool>,
+    // Actions from the clients waiting to be played, oldest first
+    moves: VecDeque<Move>,
+    // When the pile closes for slaps, and when the players who don't
+    // type are going to slap it
+    closes: Option<Instant>,
+    slaps: Vec<(Instant, usize)>,
+    // When the last slap that was played happened
+    slapped: Option<Instant>,
+    slap_window: Duration,
+    turn_timeout: Option<Duration>,
+}
+
+impl Table {
+    /// Applies an action made at `at` to the game, logs it and tells
+    /// every player what happened
+    fn play(&mut self, action: Action, at: Instant) -> Result<(), Error> {
+        let events = self.game.apply(action)
+                              .map_err(Error::other)?;
+        if let Some(ref mut log) = self.log {
+            log.action(at, action, &events).ok();
+        }
+        for event in events.iter() {
+            for player in self.players.iter_mut() {
+                player.notify(&self.game, event);
             }
         }
+        Ok(())
+    }
 
-        // Add card to pile
-        let card = self.0.hand.pop().unwrap();
-        pile.push(card);
-
-        // Send updated top three cards of the pile to client
-        send_pile(&pile, &mut writer);
-
-        Ok(pile.to_vec())
-    }
-
-    // Check for slap
-    fn slap_check(&mut self, mut pile: &mut Vec<Card>, socket: &TcpStream,
-        opponent: &mut Player) -> Result<Vec<Card>, Error> {
-
-        // Edit time computer waits for input
-        let rand = rand::thread_rng().gen_range(1, 3);
-        socket.set_read_timeout(Some(Duration::new(rand, 0))).ok();
-        let mut reader = BufReader::new(socket);
-        let mut writer = socket.try_clone().unwrap();
-        let default_time = Duration::new(3, 0);
-
-        // Wait for response from player
-        let mut response = String::new();
-        reader.read_line(&mut response).ok();
-        
-        // Determine response action
-        match response.trim() {
-            // If player thinks there's a combination
-            "space" => {
-                // Check if there's a combination and add pile to
-                // HumanPlayer's hand, otherwise add pile to 
-                // MachinePlayer's pile
-                if test_pile(&pile) {
-                    writeln!(writer, "Combination found. You won the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    self.add_to_side_pile(&mut pile).ok();
-                } else {
-                    writeln!(writer, "No combination. Computer gets the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    opponent.add_to_side_pile(&mut pile).ok();
+    /// Plays a move a client asked for. The game may have moved on since
+    /// they asked, so a move it won't take is dropped instead of ending
+    /// the game for everyone. Returns true if the move was played.
+    fn play_move(&mut self, action: Action, at: Instant) -> bool {
+        self.play(action, at).is_ok()
+    }
+
+    /// Game control function
+    fn run(&mut self) -> Result<(), Error> {
+        for player in self.players.iter_mut() {
+            player.seated(&self.game);
+        }
+        // When the player whose turn it is was asked for a card
+        let mut prompted: Option<Instant> = None;
+
+        while !self.game.is_over() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                self.tell_all("The server is shutting down. Thank you for playing!");
+                break;
+            }
+
+            // Take in everything the clients sent so far
+            while let Ok(input) = self.inputs.try_recv() {
+                self.receive(input);
+            }
+            let now = Instant::now();
+
+            // Slaps are settled before anything else
+            if self.settle_slaps(now)? {
+                prompted = None;
+                continue;
+            }
+            if self.closes.is_some_and(|at| at <= now) {
+                self.close_pile();
+            }
+
+            if self.closes.is_none() {
+                // Nobody slapped the pile, so whoever has a claim on it
+                // picks it up
+                if let Some(claim) = self.game.claim() {
+                    self.play(Action::Collect(claim.player), now)?;
+                    prompted = None;
+                    continue;
+                }
+
+                // Ask the player whose turn it is for a card
+                let turn = self.game.turn();
+                match prompted {
+                    None => {
+                        prompted = Some(now);
+                        if let Some(action) = self.players[turn].play_card(&self.game) {
+                            self.play(action, now)?;
+                            self.open_pile();
+                            prompted = None;
+                            continue;
+                        }
+                    }
+                    Some(asked) => {
+                        // Players who take too long have their card played for them
+                        if self.turn_timeout.is_some_and(|timeout| asked + timeout <= now) {
+                            self.players[turn].tell("Time's up! The server played your card.");
+                            self.play(Action::PlayCard(turn), now)?;
+                            self.open_pile();
+                            prompted = None;
+                            continue;
+                        }
+                    }
                 }
-                socket.set_read_timeout(Some(default_time)).ok();
-                return Ok(pile.to_vec());
-            },
-            "q" => return Err(Error::new(ErrorKind::Other, "Player quits!")),
-            _ => {
-                if test_pile(&pile) {
-                    writeln!(writer, "Combination found. Computer gets the pot!!\r\n").ok();
-                    writer.flush().ok();
-                    self.add_to_side_pile(&mut pile).ok();
-                    socket.set_read_timeout(Some(default_time)).ok();
-                    return Ok(pile.to_vec());
+            }
+
+            // Then whatever else the clients asked for
+            if let Some(next) = self.moves.pop_front() {
+                // Whatever a client sent before it left is dropped, like
+                // the quit that comes with hanging up after typing quit
+                if self.seats[next.client].is_none() {
+                    continue;
+                }
+                match next.action {
+                    Action::PlayCard(player) if self.closes.is_some() => {
+                        self.players[player].tell("Too early! Wait for the pile to close before you play.");
+                    }
+                    Action::PlayCard(player) => {
+                        // Cards are only played on your turn, once the
+                        // pile is closed for slaps
+                        let played = player == self.game.turn()
+                            && self.game.claim().is_none()
+                            && self.play_move(next.action, now);
+                        if played {
+                            self.open_pile();
+                            prompted = None;
+                        }
+                    }
+                    Action::Quit(_) => {
+                        self.seats[next.client] = None;
+                        self.play_move(next.action, next.at);
+                        prompted = None;
+                    }
+                    _ => {},
                 }
+                continue;
+            }
+
+            // Wait for a client to press a key, or for the next deadline.
+            // Wake up now and then to check for shutdown.
+            let idle = now + Duration::from_millis(500);
+            let timeout = match (prompted, self.turn_timeout) {
+                (Some(asked), Some(timeout)) => Some(asked + timeout),
+                _ => None,
+            };
+            let deadline = self.slaps.iter().map(|&(at, _)| at)
+                                     .chain(self.closes)
+                                     .chain(timeout)
+                                     .fold(idle, |earliest, at| earliest.min(at));
+            match self.inputs.recv_timeout(deadline - now.min(deadline)) {
+                Ok(input) => self.receive(input),
+                Err(RecvTimeoutError::Timeout) => {},
+                Err(RecvTimeoutError::Disconnected) => break,
             }
         }
-        // Ignore any other incoming key events
-        let time = 3 - rand;
-        socket.set_read_timeout(Some(Duration::new(time, 0))).ok();
-        reader.read_line(&mut response).ok();
-        match response.trim() {
-            _ => {},
+
+        if let Some(ref mut log) = self.log {
+            log.end(self.game.winner()).ok();
         }
-        socket.set_read_timeout(Some(default_time)).ok();
-        
-        Ok(pile.to_vec())
+        Ok(())
     }
 
-    // Determines if a player has all the cards
-    fn won(&mut self, writer: &mut Write) -> bool {
-        if self.0.hand.len() == 52 {
-            writeln!(writer, "Oh, too bad. You lost!!").ok();
-            writer.flush().ok();
-            return true;
+    /// Turns a message from a client into a move for the seat they sit in
+    fn receive(&mut self, input: Input) {
+        let seat = match self.seats.get(input.client) {
+            Some(&Some(seat)) => seat,
+            _ => return,
+        };
+        if let Some(action) = self.players[seat].read_action(&self.game, &input.message) {
+            self.moves.push_back(Move { client: input.client, action, at: input.at });
         }
-        false
     }
 
-    // Makes the PlayerState read only visible
-    fn state(&self) -> &PlayerState {
-        &self.0
-    }
-}
+    /// Plays the earliest of the slaps made so far, typed or not.
+    /// Returns true if someone slapped.
+    fn settle_slaps(&mut self, now: Instant) -> Result<bool, Error> {
+        let mut contenders: Vec<(Instant, usize)> = self.slaps.iter()
+            .filter(|&&(at, _)| at <= now)
+            .cloned()
+            .collect();
+        let mut others = VecDeque::new();
+        for next in self.moves.drain(..) {
+            match next.action {
+                Action::Slap(seat) => contenders.push((next.at, seat)),
+                _ => others.push_back(next),
+            }
+        }
+        self.moves = others;
+
+        let (contenders, late) = {
+            let game = &self.game;
+            order_slaps(contenders, game.turn(), self.players.len(), self.slapped,
+                        |seat| game.can_slap(seat))
+        };
+        for (at, seat) in late {
+            self.too_slow(at, seat);
+        }
+        if contenders.is_empty() {
+            return Ok(false);
+        }
+
+        let (at, winner) = contenders[0];
+        self.slapped = Some(at);
 
-/// Sends the top cards of the pile to the player (max of three)
-fn send_pile<T>(pile: &Vec<Card>, writer: &mut T) where T: Write {
- 
-    // send top three cards of the pile to client
-    if pile.len() == 0 {
-        writeln!(writer, "Pile is empty\r\n").ok();
-        writer.flush().ok();
+        self.close_pile();
+        self.play(Action::Slap(winner), at)?;
+        for &(late, seat) in contenders[1..].iter() {
+            if seat != winner {
+                self.too_slow(late, seat);
+            }
+        }
+        Ok(true)
     }
-    else if pile.len() == 1 {
-        // send card
-        writeln!(writer, "Pile:\r\n").ok();
-        writeln!(writer, "{}\r\n", pile[pile.len() - 1]).unwrap();
-        writer.flush().ok();
+
+    /// Tells a player that their slap at `at` lost the race
+    fn too_slow(&mut self, at: Instant, seat: usize) {
+        self.players[seat].tell("Too slow!");
+        if let Some(ref mut log) = self.log {
+            log.late(at, seat).ok();
+        }
     }
-    else if pile.len() == 2 {
-        let rev = &pile[pile.len() - 2..=pile.len() - 1];
-        write!(writer, "Pile:\r\n").ok();
-        
-        // send cards
-        for c in rev.iter() {
-            // send top two cards on pile
-            write!(writer, "{}\r\n", c).unwrap();
+
+    /// Sends a message from the server to every player
+    fn tell_all(&mut self, message: &str) {
+        for player in self.players.iter_mut() {
+            player.tell(message);
         }
-        writeln!(writer, "").ok();
-        writer.flush().ok();
     }
-    else {
-        let rev = &pile[pile.len() - 3..=pile.len() - 1];
-        write!(writer, "Pile:\r\n").ok();
-        
-        // send cards
-        for c in rev.iter() {
-            // send top three cards on pile
-            write!(writer, "{}\r\n", c).unwrap();
+
+    /// Opens the pile for slaps after a card hits it and finds out when
+    /// the players who don't type will slap it
+    fn open_pile(&mut self) {
+        let now = Instant::now();
+        self.closes = Some(now + self.slap_window);
+        self.slaps.clear();
+        for seat in 0..self.players.len() {
+            if self.game.has_left(seat) {
+                continue;
+            }
+            if let Some(reaction) = self.players[seat].slap_check(&self.game) {
+                self.slaps.push((now + reaction, seat));
+            }
         }
-        writeln!(writer, "").ok();
-        writer.flush().ok();
+    }
+
+    /// Closes the pile for slaps, whoever hasn't slapped yet won't
+    fn close_pile(&mut self) {
+        self.closes = None;
+        self.slaps.clear();
     }
 }
 
-/// Creates a deck of cards
-fn make_deck() -> Vec<Card> {
-    let mut deck: Vec<Card> = Vec::new();
-    for suit in [Hearts, Diamonds].iter() { //, Clubs, Spades].iter() {
-        for i in 2..11 {
-            deck.push(Card::new(Num(i), *suit));
-        }
-        for face in [Jack, Queen, King, Ace].iter() {
-            deck.push(Card::new(*face, *suit));
+/// When a slap was made and the seat that made it
+type Slap = (Instant, usize);
+
+/// Puts the slaps racing for the pile in order, the winner first. Slaps
+/// made at the same moment go to the player sitting closest after the one
+/// whose turn it is, out of `count` seats. Slaps from players who can't
+/// slap are dropped. Slaps that came in within `SLAP_GRACE` of the last
+/// one played, `slapped`, lost that race and come back on their own.
+fn order_slaps<F>(mut slaps: Vec<Slap>, turn: usize, count: usize, slapped: Option<Instant>,
+                  can_slap: F) -> (Vec<Slap>, Vec<Slap>)
+    where F: Fn(usize) -> bool
+{
+    slaps.retain(|&(_, seat)| can_slap(seat));
+    let (mut slaps, late) = match slapped {
+        Some(slapped) => {
+            let grace = slapped + Duration::from_millis(SLAP_GRACE);
+            slaps.into_iter().partition(|&(at, _)| at >= grace)
         }
-    }
-    deck
+        None => (slaps, Vec::new()),
+    };
+    slaps.sort_by_key(|&(at, seat)| (at, (seat + count - turn) % count));
+    (slaps, late)
 }
 
-/// Shuffles the deck of cards a random amount of times
-/// between 5 and 15
-fn shuffle_deck(mut deck: Vec<Card>) -> Vec<Card> {
-    let rand: usize = rand::thread_rng().gen_range(5, 15);
-    for _ in 0..rand {
-        rand::thread_rng().shuffle(&mut deck);
-    }
-    deck
+/// State shared between the lobby and the games it starts
+#[derive(Clone)]
+struct Shared {
+    // Games being played right now
+    games: Arc<AtomicUsize>,
+    // Set once the server is told to shut down
+    shutdown: Arc<AtomicBool>,
+    config: ServerConfig,
+    registry: Arc<Registry>,
 }
 
-// COMBINATION TESTS BELOW HERE
+/// Counts a game as running for as long as its thread is alive, even if
+/// the game panics
+struct GameGuard(Arc<AtomicUsize>);
 
-/// Top and second card have same rank
-fn is_pair(pile: &Vec<Card>) -> bool {
-    pile[pile.len() - 1] == pile[pile.len() - 2]
+impl Drop for GameGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
-/// Top and third card have same rank
-fn is_sandwich(pile: &Vec<Card>) -> bool {
-    pile[pile.len() - 1] == pile[pile.len() - 3]
+/// Features the server supports. Clients draw the cards themselves, so
+/// any way they like to draw them is fine.
+const FEATURES: [Feature; 2] = [Feature::Colour, Feature::Unicode];
+
+/// A client waiting in the lobby
+struct Guest {
+    socket: TcpStream,
+    // Name the client said hello with, None until it does
+    name: Option<String>,
 }
 
-/// Checks if left and right cards form (6, 9) pairing
-fn is_sixty_nine_match(left: Card, right: Card) -> bool {
-    if left == 6 && right == 9 {
-        return true;
-    }
-    if right == 6 && left == 9 {
-        return true;
+/// Sends a message to every client in the lobby that said hello
+fn tell_lobby(guests: &mut [Option<Guest>], message: &ServerMessage) {
+    for guest in guests.iter_mut() {
+        if let Some(Guest { ref mut socket, name: Some(_) }) = *guest {
+            send(socket, message);
+        }
     }
-    false
 }
 
-/// Top card and second card have ranks of 6 && 9 or 9 && 6
-fn is_sixty_nine(pile: &Vec<Card>) -> bool {
-    is_sixty_nine_match(pile[pile.len() - 1], pile[pile.len() - 2])
+/// Number of clients in the lobby that said hello
+fn greeted(guests: &[Option<Guest>]) -> usize {
+    guests.iter().filter(|g| g.as_ref().is_some_and(|g| g.name.is_some())).count()
 }
 
-/// Top card and third card have ranks 6 && 9 or 9 && 6
-fn is_sixty_nine_sandwich(pile: &Vec<Card>) -> bool {
-    is_sixty_nine_match(pile[pile.len() - 1], pile[pile.len() - 3])
+/// Whether another game can start without going over the most games the
+/// server runs at once
+fn table_free(shared: &Shared) -> bool {
+    shared.games.load(Ordering::SeqCst) < shared.config.max_games
 }
 
-/// Determines if any of the three cards form a pair
-/// returns true if their is a pair
-fn find_pair_run(left: Card, middle: Card, right: Card) -> bool {
-    if left == middle || left == right || middle == right {
-        return true;
+/// Tells a client why it can't play and hangs up on it
+fn reject(mut socket: TcpStream, reason: &str) {
+    send(&mut socket, &ServerMessage::Rejected { reason: reason.to_string() });
+    socket.shutdown(Shutdown::Both).ok();
+}
+
+/// Makes the player for a bot, starting the bot's program if it is one.
+/// Bot programs talk to the table like clients do.
+fn seat_bot(shared: &Shared, bot: &str, seat: usize, names: &[String], rng: GameRng,
+            seats: &mut Vec<Option<usize>>, inputs: &Sender<Input>) -> Result<Box<dyn Player>, Error> {
+    if let Some((_, command)) = shared.config.commands.iter().find(|&(name, _)| name == bot) {
+        let (process, reader) = start_bot(command)?;
+        spawn_reader(seats.len(), reader, inputs.clone());
+        seats.push(Some(seat));
+        return Ok(Box::new(RemotePlayer::new(seat, names.to_vec(), Box::new(process))));
     }
-    false
+    let bot = shared.registry.make(bot)
+                    .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("No bot named {}", bot)))?;
+    Ok(Box::new(BotPlayer { seat, bot, slap: None, rng }))
 }
 
-/// Determines if the ranks of cards differ by one
-fn find_abs(left: Card, right: Card) -> bool {
-    // If left is an ace, compare it as a 1 and a 14 and return true if
-    // cards are sequential
-    if is_ace(left) {
-        let value_low = left.rank.value() as i32 - right.rank.value() as i32;
-        let value_high = 14 as i32 - right.rank.value() as i32;
-        if value_low.abs() == 1 || value_high.abs() == 1 {
-            return true;
+/// Seats clients as they say hello until the host deals the cards. The
+/// first client still in the lobby is the host. A host sitting alone
+/// plays against the computer. Returns None once the server shuts down.
+fn open_table(listener: &TcpListener, shared: &Shared) -> Result<Option<Table>, Error> {
+    let (sender, inputs) = mpsc::channel();
+    let mut guests: Vec<Option<Guest>> = Vec::new();
+    listener.set_nonblocking(true)?;
+
+    loop {
+        if shared.shutdown.load(Ordering::SeqCst) {
+            for guest in guests.into_iter().flatten() {
+                reject(guest.socket, "The server is shutting down. Thank you for playing!");
+            }
+            return Ok(None);
+        }
+
+        // A full lobby is dealt in as soon as there's a table for it
+        let seated = greeted(&guests) + shared.config.bots.len();
+        if seated >= MAX_PLAYERS && table_free(shared) {
+            break;
+        }
+
+        // Let in anyone who connected, they sit down once they say hello
+        match listener.accept() {
+            Ok((socket, _addr)) => {
+                socket.set_nonblocking(false)?;
+                spawn_reader(guests.len(), BufReader::new(socket.try_clone()?), sender.clone());
+                guests.push(Some(Guest { socket, name: None }));
+                continue;
+            }
+            Err(ref err) if err.kind() == ErrorKind::WouldBlock => {},
+            Err(err) => println!("Error {}", err),
+        }
+
+        // The host deals, anyone can leave
+        if let Ok(input) = inputs.recv_timeout(Duration::from_millis(100)) {
+            let said_hello = match guests[input.client] {
+                Some(ref guest) => guest.name.is_some(),
+                None => continue,
+            };
+            let host = guests.iter().position(|g| g.as_ref().is_some_and(|g| g.name.is_some()));
+            match input.message {
+                ClientMessage::Hello { .. } if !said_hello && seated >= MAX_PLAYERS => {
+                    if let Some(guest) = guests[input.client].take() {
+                        reject(guest.socket, "The table is full, try again soon.");
+                    }
+                }
+                ClientMessage::Hello { version, ref features, ref name } if !said_hello => {
+                    let welcome = match protocol::negotiate(version, features, &FEATURES) {
+                        Ok(welcome) => welcome,
+                        Err(err) => {
+                            if let Some(guest) = guests[input.client].take() {
+                                reject(guest.socket, &err.to_string());
+                            }
+                            continue;
+                        }
+                    };
+                    if let Some(ref mut guest) = guests[input.client] {
+                        send(&mut guest.socket, &welcome);
+                        guest.name = Some(name.trim().to_string());
+                    }
+
+                    let seated = greeted(&guests) + shared.config.bots.len();
+                    tell_lobby(&mut guests, &ServerMessage::Lobby { players: seated });
+                    if seated >= MAX_PLAYERS {
+                        if table_free(shared) {
+                            break;
+                        }
+                        let notice = "Every table is busy, the cards are dealt once a game ends.";
+                        tell_lobby(&mut guests, &ServerMessage::Notice(notice.to_string()));
+                    }
+                }
+                ClientMessage::Hello { .. } => {},
+                ClientMessage::Quit => {
+                    guests[input.client] = None;
+                    let seated = greeted(&guests) + shared.config.bots.len();
+                    tell_lobby(&mut guests, &ServerMessage::Lobby { players: seated });
+                }
+                // Clients have to say hello before anything else
+                _ if !said_hello => {
+                    if let Some(guest) = guests[input.client].take() {
+                        reject(guest.socket, &HandshakeError::NoHello.to_string());
+                    }
+                }
+                ClientMessage::Deal if host == Some(input.client) => {
+                    if table_free(shared) {
+                        break;
+                    }
+                    if let Some(ref mut guest) = guests[input.client] {
+                        send(&mut guest.socket, &ServerMessage::TablesFull);
+                    }
+                }
+                _ => {},
+            }
         }
     }
-    // If right is an ace, compare it as a 1 and a 14 and return true if 
-    // cards are sequential
-    if is_ace(right) {
-        let value_low = left.rank.value() as i32 - right.rank.value() as i32;
-        let value_high = left.rank.value() as i32 - 14 as i32;
-        if value_low.abs() == 1 || value_high.abs() == 1 {
-            return true;
+    listener.set_nonblocking(false)?;
+
+    // Seat the clients in the order they said hello, anyone still
+    // shaking hands missed this game
+    let mut seats = Vec::new();
+    let mut names = Vec::new();
+    let mut sockets = Vec::new();
+    for guest in guests.into_iter() {
+        match guest {
+            Some(Guest { socket, name: Some(name) }) => {
+                seats.push(Some(names.len()));
+                if name.is_empty() {
+                    names.push(format!("Player {}", names.len() + 1));
+                } else {
+                    names.push(name);
+                }
+                sockets.push(socket);
+            }
+            Some(guest) => {
+                reject(guest.socket, "The cards were dealt before you said hello.");
+                seats.push(None);
+            }
+            None => seats.push(None),
         }
     }
-    // Otherwise just check for sequentail cards
-    let value = left.rank.value() as i32 - right.rank.value() as i32;
-    if value.abs() == 1 {
-        return true;
+    // Then the bots, a player sitting alone gets one to play against
+    let config = &shared.config;
+    let mut bots = Vec::new();
+    if config.bots.is_empty() && names.len() == 1 {
+        bots.push((config.difficulty.name().to_string(), "Computer".to_string()));
+    }
+    for (i, bot) in config.bots.iter().enumerate() {
+        let name = if config.bots.len() == 1 {
+            format!("Computer ({})", bot)
+        } else {
+            format!("Computer {} ({})", i + 1, bot)
+        };
+        bots.push((bot.clone(), name));
+    }
+    names.extend(bots.iter().map(|(_, name)| name.clone()));
+
+    // Everything random at the table comes from the seed
+    let seed = config.seed.unwrap_or_else(random_seed);
+    let mut rng = seeded(seed);
+    let game = GameState::shuffled(config.deck.build(), names.len(), &mut rng)
+                   .with_rules(config.rules)
+                   .with_penalty(config.penalty);
+
+    // Start the bots before seating anyone, so the players hear about
+    // a bot that won't start
+    let mut seated_bots = Vec::new();
+    for (i, (bot, _)) in bots.iter().enumerate() {
+        let seat = sockets.len() + i;
+        match seat_bot(shared, bot, seat, &names, seeded(rng.gen()), &mut seats, &sender) {
+            Ok(player) => seated_bots.push(player),
+            Err(err) => {
+                let reason = format!("The bot {} couldn't start: {}", bot, err);
+                for socket in sockets {
+                    reject(socket, &reason);
+                }
+                return Err(err);
+            }
+        }
     }
-    false
-}
 
-/// Check a card for an ace value
-fn is_ace(card: Card) -> bool {
-    if card.rank.value() == 1 {
-        return true;
+    let mut players: Vec<Box<dyn Player>> = Vec::new();
+    for socket in sockets {
+        let seat = players.len();
+        players.push(Box::new(RemotePlayer::new(seat, names.clone(), Box::new(socket))));
     }
-    false
+    players.extend(seated_bots);
+
+    let header = Header {
+        seed,
+        deck: config.deck,
+        rules: config.rules,
+        names,
+        penalty: config.penalty,
+    };
+    let (log, log_path) = match config.logs {
+        Some(ref dir) => match start_log(Path::new(dir), &header) {
+            Ok((log, path)) => (Some(log), Some(path)),
+            Err(err) => {
+                println!("Can't log the game in {}: {}", dir, err);
+                (None, None)
+            }
+        },
+        None => (None, None),
+    };
+
+    Ok(Some(Table {
+        seed,
+        log,
+        log_path,
+        game,
+        players,
+        inputs,
+        seats,
+        shutdown: shared.shutdown.clone(),
+        moves: VecDeque::new(),
+        closes: None,
+        slaps: Vec::new(),
+        slapped: None,
+        slap_window: config.slap_window,
+        turn_timeout: config.turn_timeout,
+    }))
 }
 
-/// Top three cards form a run in any order
-fn is_run(pile: &Vec<Card>) -> bool {
-    let left = pile[pile.len() - 1];
-    let middle = pile[pile.len() - 2];
-    let right = pile[pile.len() - 3];
-
-    // If no cards have equal rank, search for a run
-    if !find_pair_run(left, middle, right) {
-        let one_two = find_abs(left, middle);
-        let one_three = find_abs(left, right);
-        let two_three = find_abs(middle, right);
+/// Starts the log of a game in a new file in `dir`, named after when the
+/// game started and its seed
+fn start_log(dir: &Path, header: &Header) -> Result<(Recorder<File>, PathBuf), Error> {
+    fs::create_dir_all(dir)?;
+    let started = SystemTime::now().duration_since(UNIX_EPOCH)
+                                   .map(|since| since.as_secs())
+                                   .unwrap_or(0);
+    let path = dir.join(format!("{}-{}.log", started, header.seed));
+    let log = Recorder::new(File::create(&path)?, header)?;
+    Ok((log, path))
+}
 
-        // If the first and second cards differ by one rank
-        // and the second and third cards differ by one rank
-        // (e.g. 3->4->5)
-        if one_two && two_three {
-            return true;
+/// Shuts the server down once `shutdown` is typed into its terminal. A
+/// server without a terminal, run as a service, keeps running.
+fn watch_stdin(shutdown: Arc<AtomicBool>) {
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        for line in stdin.lock().lines() {
+            match line {
+                Ok(ref line) if line.trim() == "shutdown" => {
+                    shutdown.store(true, Ordering::SeqCst);
+                    return;
+                }
+                Ok(_) => {},
+                Err(_) => return,
+            }
         }
+    });
+}
 
-        // If the first and third cards differ by one rank
-        // and the second and third cards differ by one rank
-        // (e.g. 3->5->4)
-        if one_three && two_three {
-            return true;
+/// Command-line help
+const USAGE: &str = "Usage: server [options]
+
+Options:
+    --config FILE         read settings from a TOML file, flags win over it
+    --host ADDRESS        address to listen on (0.0.0.0)
+    --port PORT           port to listen on (24794)
+    --rules classic|all   slap rules to start from (classic)
+    --with RULES          turn on rules, separated by commas
+    --without RULES       turn off rules, separated by commas
+    --decks N             standard decks shuffled together (1)
+    --lowest N            lowest number card kept in each suit (2)
+    --jokers N            jokers added to each deck (0)
+    --slap-window SECS    how long the pile stays open for slaps (3)
+    --turn-timeout SECS   seconds before a card is played for a slow player,
+                          0 to wait forever (0)
+    --penalty PENALTY     what slapping without a combination costs: burn:N,
+                          give-pile or lock-out (burn:1)
+    --seed N              seed for the shuffle and the bots, to play the same
+                          game again (random)
+    --logs DIR            folder the game logs are written to, empty to not
+                          log games (logs)
+    --max-games N         most games played at once (16)
+    --difficulty LEVEL    computer playing a player who sits alone: easy,
+                          normal, hard or expert (normal)
+    --bots NAMES          bots seated at every table, separated by commas
+    --command NAME=PROGRAM
+                          run PROGRAM as the bot NAME, it speaks the client
+                          protocol over its stdin and stdout
+
+Rules: pair, sandwich, sixty_nine, sixty_nine_sandwich, run, top_bottom,
+marriage, divorce, add_to_ten, tens, four_in_a_row, jokers";
+
+/// Bots the server can seat by name. Register your own bots here.
+fn registry() -> Registry {
+    Registry::builtin()
+}
+
+fn main() {
+    let config: ServerConfig = match config::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            println!("{}\n\n{}", err, USAGE);
+            process::exit(2);
         }
+    };
 
-        // If the first and second cards differ by one rank
-        // and the first and third card differ by one rank
-        // (e.g. 4->5->3)
-        if one_two && one_three {
-            return true;
+    let registry = registry();
+    for bot in config.bots.iter() {
+        if registry.make(bot).is_none() && !config.commands.iter().any(|(name, _)| name == bot) {
+            println!("No bot named {}. Bots: {}", bot, registry.names().join(", "));
+            process::exit(2);
         }
     }
-    false
-}
-
-/// Tests for different combinations
-fn test_pile(pile: &Vec<Card>) -> bool {
-    // If pile has 0 or 1 card, there is no combination
-    if pile.len() < 2 {
-        return false
+    if config.bots.len() >= MAX_PLAYERS {
+        println!("At most {} bots fit at a table", MAX_PLAYERS - 1);
+        process::exit(2);
     }
-    // If pile has 2 or more cards, check for combinations that
-    // require at least 2 cards
-    if pile.len() >= 2 {
-        if is_pair(pile) {
-            return true;
-        }
-        // tests for a sixty-nine combo
-        else if is_sixty_nine(pile) {
-            return true;
+
+    // Creates Tcp connection
+    let address = format!("{}:{}", config.host, config.port);
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            println!("Can't listen on {}: {}", address, err);
+            process::exit(1);
         }
-        // If pile has more than two cards, check for combinations
-        // that require at least three cards
-        if pile.len() > 2 {
-            // tests for a pair sandwich
-            if is_sandwich(pile) {
-                return true;
-            }
-            // tests for a sixty-nine combo
-            else if is_sixty_nine_sandwich(pile) {
-                return true;
-            }
-            // tests for a run of three cards
-            else if is_run(pile) {
-                return true;
+    };
+    let shared = Shared {
+        games: Arc::new(AtomicUsize::new(0)),
+        shutdown: Arc::new(AtomicBool::new(false)),
+        config,
+        registry: Arc::new(registry),
+    };
+    watch_stdin(shared.shutdown.clone());
+    println!("Listening on {}, type shutdown to stop the server", address);
+
+    // Every table plays in its own thread, so a game that panics
+    // doesn't take the others down with it
+    let mut count = 0;
+    loop {
+        let mut table = match open_table(&listener, &shared) {
+            Ok(Some(table)) => table,
+            Ok(None) => break,
+            Err(e) => {
+                println!("Error {}", e);
+                continue;
             }
+        };
+        count += 1;
+        let id = count;
+        shared.games.fetch_add(1, Ordering::SeqCst);
+        let guard = GameGuard(shared.games.clone());
+        println!("Game {} started with {} players, seed {}", id, table.game.num_players(), table.seed);
+        if let Some(ref path) = table.log_path {
+            println!("Game {} is logged in {}", id, path.display());
         }
-    } 
-    false
-}
 
-/// Deals the cards to the client and server hands
-//fn deal_hands(deck: &mut Vec<Card>, socket: &TcpStream) -> Vec<Card> {
-fn deal_hands(deck: &mut Vec<Card>, machine: &mut MachinePlayer,
-              human: &mut HumanPlayer)  {
+        thread::spawn(move || {
+            let _guard = guard;
+            match panic::catch_unwind(AssertUnwindSafe(|| table.run())) {
+                Ok(Ok(())) => println!("Game {} is over", id),
+                Ok(Err(e)) => println!("Game {} ended with error {}", id, e),
+                Err(_) => {
+                    println!("Game {} crashed", id);
+                    table.tell_all("Sorry, the game crashed!");
+                }
+            }
+        });
+    }
 
-    // deal card to player and self
-    for _ in 0..(deck.len()/2) {
-        human.add_to_hand(deck.pop().unwrap()).ok();
-        machine.add_to_hand(deck.pop().unwrap()).ok();
+    // Let the games say goodbye before exiting
+    while shared.games.load(Ordering::SeqCst) > 0 {
+        thread::sleep(Duration::from_millis(100));
     }
 }
 
-/// Game control function
-//fn play_game<T, U>(mut reader: T, mut writer: U) -> 
-fn play_game(socket: &TcpStream) ->
-    Result<(), Error> 
-    //where T: BufRead, U: Write 
-{
-    let mut deck: Vec<Card> = shuffle_deck(make_deck());
-    let mut pile: Vec<Card> = Vec::new();
-    let mut machine = MachinePlayer(PlayerState::new());
-    let mut human = HumanPlayer(PlayerState::new());
-    let mut writer = socket.try_clone().unwrap();
-    let mut reader = BufReader::new(socket);
-    
-    deal_hands(&mut deck, &mut machine, &mut human);
-    
-    // Let the client who plays first
-    let mut turn = random::<usize>() % 2;
-    if turn == 0 {
-        write!(writer, "Computer goes first!\r\n").ok();
-        writer.flush().ok();
-    } else {
-        write!(writer, "You go first!\r\n").ok();
-        writer.flush().ok();
-    }
-    
-    
-    loop {
-        //let rand: u64 = rand::thread_rng().gen_range(1, 3);
-        //socket.set_read_timeout(Some(Duration::new(rand, 0))).ok();
-        socket.set_read_timeout(Some(Duration::new(3, 0))).ok();
-        
-        //let mut response = String::new();
-        let player: &mut Player;
-        let opponent: &mut Player;
-        
-        // Machine plays if turn == 0
-        // Human plays if turn == 1
-        if turn % 2 == 0 {
-            player = &mut machine;
-            opponent = &mut human;
-        } else {
-            player = &mut human;
-            opponent = &mut machine;
+
+#[cfg(test)]
+mod tests {
+    extern crate card;
+    use super::*;
+    use card::{Card, Rank::*, Suit};
+    use std::sync::Mutex;
+
+    // Sits at the table, types what its client does and remembers what
+    // it was told
+    struct Typist(usize, Arc<Mutex<Vec<String>>>);
+
+    impl Player for Typist {
+        fn play_card(&mut self, _game: &GameState) -> Option<Action> {
+            None
         }
 
-        // Play a card from players hand
-        match player.play_card(&mut pile, &mut reader, &mut writer, opponent) {
-            Ok(updated_pile) => pile = updated_pile,
-            Err(err) => {
-                writeln!(writer, "{}\r\n", err).ok();
-                return Ok(());
+        fn read_action(&mut self, _game: &GameState, message: &ClientMessage) -> Option<Action> {
+            match *message {
+                ClientMessage::PlayCard => Some(Action::PlayCard(self.0)),
+                ClientMessage::Quit => Some(Action::Quit(self.0)),
+                _ => None,
             }
         }
-        // Check for slap
-        player.slap_check(&mut pile, socket, opponent).ok();
 
-        // Determine if a player has won the game
-        if player.won(&mut writer) {
-            break;
+        fn slap_check(&mut self, _game: &GameState) -> Option<Duration> {
+            None
         }
-        turn += 1;
-    }
-    
-    Ok(())
-}
 
-fn main() {
-    // Creates Tcp connection
-    let address = ("0.0.0.0:24794").parse::<SocketAddr>().unwrap();
-    let listener = TcpListener::bind(&address).unwrap();
+        fn seated(&mut self, _game: &GameState) {}
 
-    match listener.accept() {
-        Ok((socket, _addr)) => {
-            //play_game(reader, writer).ok();
-            play_game(&socket).ok();
+        fn notify(&mut self, _game: &GameState, _event: &Event) {}
+
+        fn tell(&mut self, message: &str) {
+            self.1.lock().unwrap().push(message.to_string());
         }
-        Err(e) => {
-            println!("Error {}", e);
+    }
+
+    // A table of typists, each seated in the order their client joined
+    fn table(players: usize) -> (Table, Sender<Input>) {
+        let (sender, inputs) = mpsc::channel();
+        let table = Table {
+            seed: 1,
+            log: None,
+            log_path: None,
+            game: GameState::new(card::make_deck(), players, 0),
+            players: (0..players).map(|seat| Box::new(Typist(seat, Default::default())) as Box<dyn Player>)
+                                 .collect(),
+            inputs,
+            seats: (0..players).map(Some).collect(),
+            shutdown: Arc::new(AtomicBool::new(false)),
+            moves: VecDeque::new(),
+            closes: None,
+            slaps: Vec::new(),
+            slapped: None,
+            slap_window: Duration::from_secs(3),
+            turn_timeout: None,
+        };
+        (table, sender)
+    }
+
+    fn quit(client: usize) -> Input {
+        Input { client, message: ClientMessage::Quit, at: Instant::now() }
+    }
+
+    #[test]
+    fn test_quitting_twice() {
+        // A client types quit and hangs up, which quits again. Both
+        // arrive before the table gets to either.
+        let (mut table, sender) = table(3);
+        for &client in [0, 0, 1].iter() {
+            sender.send(quit(client)).unwrap();
         }
+        table.run().unwrap();
+
+        assert!(table.game.has_left(0) && table.game.has_left(1));
+        assert_eq!(table.game.winner(), Some(2));
+    }
+
+    #[test]
+    fn test_playing_too_early() {
+        // Player 0 plays while the pile is still open for slaps, then
+        // player 1 leaves
+        let (mut table, sender) = table(2);
+        let told = Arc::new(Mutex::new(Vec::new()));
+        table.players[0] = Box::new(Typist(0, told.clone()));
+        table.closes = Some(Instant::now() + Duration::from_secs(60));
+        sender.send(Input { client: 0, message: ClientMessage::PlayCard, at: Instant::now() }).unwrap();
+        sender.send(quit(1)).unwrap();
+        table.run().unwrap();
+
+        // Player 0 still has all their cards, and was told why
+        assert_eq!(table.game.player(0).card_count(), 26);
+        assert!(told.lock().unwrap()[0].starts_with("Too early!"));
     }
-}
 
+    #[test]
+    fn test_slap_ties() {
+        let start = Instant::now();
+        let slaps = vec![(start, 0), (start, 3), (start, 2)];
+
+        // Player 2 sits right after player 1, whose turn it is, and
+        // player 0 goes last
+        let (order, late) = order_slaps(slaps, 1, 4, None, |_| true);
+        assert_eq!(order, vec![(start, 2), (start, 3), (start, 0)]);
+        assert!(late.is_empty());
+
+        // The quickest slap still wins
+        let later = start + Duration::from_millis(1);
+        let (order, _) = order_slaps(vec![(later, 2), (start, 0)], 1, 4, None, |_| true);
+        assert_eq!(order[0], (start, 0));
+    }
 
-#[cfg(test)]
-mod tests {
-    extern crate card;
-    use card::{Card, Rank, Rank::*, Suit, Suit::*};
+    #[test]
+    fn test_slap_grace() {
+        let slapped = Instant::now();
+        let behind = slapped + Duration::from_millis(SLAP_GRACE - 1);
+        let after = slapped + Duration::from_millis(SLAP_GRACE);
+        let (order, late) = order_slaps(vec![(behind, 1), (after, 0)], 0, 2, Some(slapped), |_| true);
+
+        assert_eq!(order, vec![(after, 0)]);
+        assert_eq!(late, vec![(behind, 1)]);
+    }
+
+    #[test]
+    fn test_locked_out_slaps() {
+        let start = Instant::now();
+        let later = start + Duration::from_millis(5);
+        // The quicker players 0 and 2 are locked out, so player 1 wins
+        let slaps = vec![(start, 0), (later, 1), (start, 2)];
+        let (order, late) = order_slaps(slaps, 0, 3, None, |seat| seat == 1);
+
+        assert_eq!(order, vec![(later, 1)]);
+        assert!(late.is_empty());
+    }
+
+    #[test]
+    fn test_bots_always_play() {
+        let mut player = BotPlayer { seat: 1, bot: Registry::builtin().make("easy").unwrap(), slap: None, rng: seeded(1) };
+        let game = GameState::new(card::make_deck(), 2, 1);
+
+        assert_eq!(player.play_card(&game), Some(Action::PlayCard(1)));
+    }
 
     #[test]
     fn test_card_creation() {
@@ -0,0 +1,227 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Games between computer players for Egyptian RatScrew
+
+//! Games played by bots alone, without a server or any clients.
+//!
+//! Time only moves in the bots' heads: when a card lands every bot says how
+//! long it would take to slap it, and the quickest slap inside the slap
+//! window is played straight away. Slaps made at the same moment go to the
+//! player sitting closest after the one whose turn it is, the same as on the
+//! server. Nothing waits, so thousands of games take seconds.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use GameRng;
+use bot::{Bot, Decision, View};
+use engine::{Action, ActionError, Event, GameState};
+use rules::SlapPattern;
+
+/// Cards played before a game is called a draw. Some deals go round in
+/// circles forever when nobody slaps.
+pub const MAX_CARDS: u64 = 20_000;
+
+/// What happened over a number of games
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Games played
+    pub games: u64,
+    /// Games won by each seat
+    pub wins: Vec<u64>,
+    /// Games called a draw after `MAX_CARDS` cards
+    pub draws: u64,
+    /// Cards laid on the pile
+    pub cards_played: u64,
+    /// Face cards that started a challenge
+    pub challenges: u64,
+    /// Piles won by slapping, by the combination slapped
+    pub slaps: HashMap<SlapPattern, u64>,
+    /// Slaps on piles without a combination
+    pub false_slaps: u64,
+}
+
+impl Stats {
+    pub fn new(players: usize) -> Self {
+        Stats {
+            games: 0,
+            wins: vec![0; players],
+            draws: 0,
+            cards_played: 0,
+            challenges: 0,
+            slaps: HashMap::new(),
+            false_slaps: 0,
+        }
+    }
+
+    /// Share of the games a seat won, between 0 and 1
+    pub fn win_rate(&self, seat: usize) -> f64 {
+        self.per_game(self.wins[seat])
+    }
+
+    /// Average of a count over every game played
+    pub fn per_game(&self, count: u64) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        count as f64 / self.games as f64
+    }
+
+    /// Piles won by slapping a combination
+    pub fn slapped(&self, pattern: SlapPattern) -> u64 {
+        self.slaps.get(&pattern).cloned().unwrap_or(0)
+    }
+
+    /// Counts an event
+    fn record(&mut self, event: &Event) {
+        match *event {
+            Event::CardPlayed { .. } => self.cards_played += 1,
+            Event::ChallengeStarted { .. } => self.challenges += 1,
+            Event::Slapped { ref found, .. } => *self.slaps.entry(found.pattern).or_insert(0) += 1,
+            Event::FalseSlap { .. } => self.false_slaps += 1,
+            Event::GameWon { player } => self.wins[player] += 1,
+            _ => {},
+        }
+    }
+}
+
+/// Plays a game to the end between bots seated in order, and adds what
+/// happened to the stats. The bots draw their random numbers from `rng`.
+pub fn play_game(mut game: GameState, bots: &mut [Box<dyn Bot>], slap_window: Duration,
+                 rng: &mut GameRng, stats: &mut Stats) -> Result<(), ActionError> {
+    let count = bots.len();
+    for (seat, bot) in bots.iter_mut().enumerate() {
+        bot.seated(&View::new(&game, seat));
+    }
+    stats.games += 1;
+
+    // How long after the last card landed each bot will slap it
+    let mut slaps: Vec<Option<Duration>> = vec![None; count];
+    let mut played = 0;
+    while !game.is_over() {
+        if played >= MAX_CARDS {
+            stats.draws += 1;
+            return Ok(());
+        }
+
+        let turn = game.turn();
+        let quickest = slaps.iter()
+            .enumerate()
+            .filter_map(|(seat, slap)| slap.map(|at| (at, seat)))
+            .filter(|&(at, seat)| at <= slap_window && game.can_slap(seat))
+            .min_by_key(|&(at, seat)| (at, (seat + count - turn) % count));
+        let action = match quickest {
+            Some((_, seat)) => Action::Slap(seat),
+            None => match game.claim() {
+                // Nobody slapped the pile, so whoever has a claim on it
+                // picks it up
+                Some(claim) => Action::Collect(claim.player),
+                None => {
+                    bots[turn].turn(&View::new(&game, turn));
+                    played += 1;
+                    Action::PlayCard(turn)
+                }
+            },
+        };
+
+        // The pile only opens for slaps when a card lands on it
+        let opens = matches!(action, Action::PlayCard(_));
+        for slap in slaps.iter_mut() {
+            *slap = None;
+        }
+        for event in game.apply(action)? {
+            stats.record(&event);
+            for (seat, bot) in bots.iter_mut().enumerate() {
+                match bot.observe(&View::new(&game, seat), &event, rng) {
+                    Decision::Slap if opens => slaps[seat] = Some(Duration::from_millis(0)),
+                    Decision::SlapAfter(delay) if opens => slaps[seat] = Some(delay),
+                    _ => {},
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {Card, Deck};
+    use ai::Difficulty;
+    use bot::{ReflexBot, Stubborn};
+    use {Rank::*, Suit::*};
+
+    // Slaps every combination this many milliseconds after it lands
+    struct Quick(u64);
+
+    impl Bot for Quick {
+        fn observe(&mut self, view: &View, event: &Event, _rng: &mut GameRng) -> Decision {
+            match *event {
+                Event::CardPlayed { .. } if view.rules().matches(view.pile().cards()) => {
+                    Decision::SlapAfter(Duration::from_millis(self.0))
+                }
+                _ => Decision::Pass,
+            }
+        }
+    }
+
+    // Plays games between an expert and an easy bot
+    fn play(games: usize, seed: u64) -> Stats {
+        let mut rng = ::seeded(seed);
+        let mut stats = Stats::new(2);
+        for _ in 0..games {
+            let mut bots: Vec<Box<dyn Bot>> = vec![Box::new(ReflexBot::new(Difficulty::Expert.reflexes())),
+                                               Box::new(ReflexBot::new(Difficulty::Easy.reflexes()))];
+            let game = GameState::shuffled(::make_deck(), 2, &mut rng);
+            play_game(game, &mut bots, Duration::from_secs(3), &mut rng, &mut stats).unwrap();
+        }
+        stats
+    }
+
+    #[test]
+    fn test_games_end() {
+        let stats = play(20, 1);
+
+        assert_eq!(stats.games, 20);
+        assert_eq!(stats.wins[0] + stats.wins[1] + stats.draws, 20);
+        assert!(stats.cards_played > 0 && stats.challenges > 0);
+        assert!(stats.per_game(stats.cards_played) > 10.0);
+    }
+
+    #[test]
+    fn test_seed_replays_games() {
+        assert_eq!(play(5, 9), play(5, 9));
+        assert!(play(5, 9) != play(5, 10));
+    }
+
+    #[test]
+    fn test_quickest_slap_wins() {
+        // Player 1 lays a three on player 0's three and slaps it first
+        let deck = Deck::from(vec![Card::new(Num(3), Hearts), Card::new(Num(3), Spades),
+                                   Card::new(Num(7), Clubs), Card::new(Num(9), Diamonds)]);
+        let mut stats = Stats::new(2);
+        let mut bots: Vec<Box<dyn Bot>> = vec![Box::new(Quick(500)), Box::new(Quick(200))];
+        play_game(GameState::new(deck.clone(), 2, 0), &mut bots, Duration::from_secs(3), &mut ::seeded(1), &mut stats).unwrap();
+
+        assert!(stats.slapped(SlapPattern::Pair) > 0);
+        assert_eq!(stats.wins, vec![0, 1]);
+
+        // Nobody is quick enough for a short slap window
+        let mut stats = Stats::new(2);
+        play_game(GameState::new(deck, 2, 0), &mut bots, Duration::from_millis(100), &mut ::seeded(1), &mut stats).unwrap();
+        assert_eq!(stats.slapped(SlapPattern::Pair), 0);
+    }
+
+    #[test]
+    fn test_bots_always_play() {
+        // Player 0 lays a three, player 1 lays a four and player 0 runs
+        // out of cards
+        let deck = Deck::from(vec![Card::new(Num(4), Clubs), Card::new(Num(3), Hearts)]);
+        let mut stats = Stats::new(2);
+        let mut bots: Vec<Box<dyn Bot>> = vec![Box::new(Stubborn), Box::new(Stubborn)];
+        play_game(GameState::new(deck, 2, 0), &mut bots, Duration::from_secs(3), &mut ::seeded(1), &mut stats).unwrap();
+
+        assert_eq!(stats.cards_played, 2);
+        assert_eq!(stats.wins, vec![0, 1]);
+    }
+}
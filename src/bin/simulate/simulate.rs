@@ -0,0 +1,109 @@
+// MIT License
+// Copyright (c) 2018 Cole Phares
+// Last Modified: 10/18/2026
+// Plays Egyptian RatScrew between computer players to compare rules and bots
+
+extern crate card;
+use card::seeded;
+use card::bot::{Bot, Registry};
+use card::config::{self, ConfigError, SimulateConfig};
+use card::engine::{GameState, MAX_PLAYERS};
+use card::rules::SlapPattern;
+use card::sim::{self, Stats};
+use std::env;
+use std::process;
+
+const USAGE: &str = "Usage: simulate [options]
+
+Plays games between bots and reports how they went. The same seed and
+settings play the same deals.
+
+Options:
+    --config FILE         read settings from a TOML file, flags win over it
+    --games N             games to play (1000)
+    --seed N              seed for every random number in the games (1)
+    --bots NAMES          bots at the table in seat order, separated by
+                          commas (normal,normal)
+    --rules classic|all   slap rules to start from (classic)
+    --with RULES          turn on rules, separated by commas
+    --without RULES       turn off rules, separated by commas
+    --decks N             standard decks shuffled together (1)
+    --lowest N            lowest number card kept in each suit (2)
+    --jokers N            jokers added to each deck (0)
+    --slap-window SECS    how long the pile stays open for slaps (3)
+    --penalty PENALTY     what slapping without a combination costs: burn:N,
+                          give-pile or lock-out (burn:1)
+
+Bots: easy, normal, hard, expert
+
+Rules: pair, sandwich, sixty_nine, sixty_nine_sandwich, run, top_bottom,
+marriage, divorce, add_to_ten, tens, four_in_a_row, jokers";
+
+/// Prints what happened over every game
+fn report(config: &SimulateConfig, stats: &Stats) {
+    println!("Played {} games between {} bots, seed {}\n",
+             stats.games, config.bots.len(), config.seed);
+    println!("Seat  Bot         Wins    Rate");
+    for (seat, bot) in config.bots.iter().enumerate() {
+        println!("{:<4}  {:<10} {:>5}  {:>5.1}%",
+                 seat + 1, bot, stats.wins[seat], stats.win_rate(seat) * 100.0);
+    }
+    if stats.draws > 0 {
+        println!("Draws after {} cards: {}", sim::MAX_CARDS, stats.draws);
+    }
+
+    println!("\nPer game:");
+    println!("    {:<24} {:>8.1}", "cards played", stats.per_game(stats.cards_played));
+    println!("    {:<24} {:>8.1}", "challenges", stats.per_game(stats.challenges));
+    for &pattern in SlapPattern::all().iter() {
+        if config.rules.allows(pattern) {
+            println!("    {:<24} {:>8.2}", format!("{:?} slaps", pattern), stats.per_game(stats.slapped(pattern)));
+        }
+    }
+    println!("    {:<24} {:>8.2}", "false slaps", stats.per_game(stats.false_slaps));
+}
+
+fn main() {
+    let config: SimulateConfig = match config::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(ConfigError::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            println!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let registry = Registry::builtin();
+    for bot in config.bots.iter() {
+        if registry.make(bot).is_none() {
+            println!("No bot named {}. Bots: {}", bot, registry.names().join(", "));
+            process::exit(2);
+        }
+    }
+    if config.bots.len() < 2 || config.bots.len() > MAX_PLAYERS {
+        println!("A table needs between 2 and {} bots", MAX_PLAYERS);
+        process::exit(2);
+    }
+
+    let mut rng = seeded(config.seed);
+    let mut stats = Stats::new(config.bots.len());
+    for game in 0..config.games {
+        let table = GameState::shuffled(config.deck.build(), config.bots.len(), &mut rng)
+                             .with_rules(config.rules)
+                             .with_penalty(config.penalty);
+
+        // Fresh bots every game, so nothing carries over between games
+        let mut bots: Vec<Box<dyn Bot>> = config.bots.iter()
+                                            .filter_map(|name| registry.make(name))
+                                            .collect();
+        if let Err(err) = sim::play_game(table, &mut bots, config.slap_window, &mut rng, &mut stats) {
+            println!("Game {} went wrong: {}", game + 1, err);
+            process::exit(1);
+        }
+    }
+
+    report(&config, &stats);
+}